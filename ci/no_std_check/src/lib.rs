@@ -0,0 +1,39 @@
+//! Builds only if the `alloc` APIs of tiny-rng are available without
+//! std. Run `cargo build` here, preferably for a bare-metal target
+//! such as `thumbv7em-none-eabi`.
+
+#![no_std]
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use tiny_rng::{FenwickSampler, Mixture, Rand, RandObj, Rng, ShuffleBuffer};
+
+pub fn use_alloc_apis(seed: u64) -> Vec<u8> {
+    let mut rng = Rng::from_seed(seed);
+    let mut a = [1, 2, 3, 4];
+    let mut d: VecDeque<u32> = (0..4).collect();
+    let mut out = rng.rand_bytes(3);
+    out.extend(rng.rand_bits(3).iter().map(|&b| b as u8));
+    out.extend(rng.rand_bitset(65).iter().map(|&x| x as u8));
+    out.extend(rng.rand_utf8_string(8).bytes());
+    out.extend(rng.shuffle_tracked(&mut a).iter().map(|&i| i as u8));
+    let log = rng.shuffle_recording(&mut a);
+    tiny_rng::unshuffle(&mut a, &log);
+    rng.shuffle_deque(&mut d);
+    out.extend(rng.choice_excluding(4, &[0]).map(|i| i as u8));
+    out.extend(rng.sample_fraction(&a, 0.5).iter().map(|&&x| x as u8));
+    out.extend(rng.draw_indices(4).map(|i| i as u8));
+    out.extend(rng.random_walk_1d(4).iter().map(|&x| x as u8));
+    out.extend(rng.rand_composition(10, 3).iter().map(|&x| x as u8));
+    out.extend(rng.random_maze(2, 2).iter().map(|&w| w as u8));
+    out.push(rng.choice_avoiding_recent(4, &[1], 0.5) as u8);
+    out.push(FenwickSampler::new(&[1.0, 2.0]).sample(&mut rng) as u8);
+    out.extend(ShuffleBuffer::new(2).push(1_u8, &mut rng));
+    let mixture = Mixture::new(alloc::vec![(1.0, Box::new(|_: &mut Rng| 1.0) as _)]);
+    out.push(mixture.sample(&mut rng) as u8);
+    let mut boxed: Box<dyn RandObj> = Box::new(rng);
+    out.push(boxed.rand_u32() as u8);
+    out
+}
@@ -25,6 +25,15 @@ let mut a = [1, 2, 3, 4];
 rng.shuffle(&mut a);
 println!("{:?}", a);
 ```
+
+### Features
+
+* `std`: Seeding from the system time and methods that need floating
+  point functions, like `rand_normal_f64`. Implies `alloc`.
+* `alloc`: Methods that return a `Vec` or `String`, for `no_std`
+  targets with an allocator.
+* `stats`: Counting of rejected draws, see `Rng::rejection_count`.
+* `simd`: Bulk generation by `Rng::fill_simd`.
 */
 
 #![cfg_attr(not(test), no_std)]
@@ -32,6 +41,9 @@ println!("{:?}", a);
 #[cfg(feature = "std")]
 extern crate std;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 fn wrapping_next_power_of_two_u32(x: u32) -> u32 {
     const H: u32 = 1 << 31;
     if x <= H {x.next_power_of_two()} else {0}
@@ -42,6 +54,393 @@ fn wrapping_next_power_of_two_u64(x: u64) -> u64 {
     if x <= H {x.next_power_of_two()} else {0}
 }
 
+#[cfg(feature = "std")]
+/// The logarithm of `k!`, from a table for small `k`
+/// and from the Stirling series otherwise.
+fn ln_factorial(k: f64) -> f64 {
+    const TABLE: [f64; 10] = [
+        0.0, 0.0, core::f64::consts::LN_2, 1.791759469228055,
+        3.1780538303479458, 4.787491742782046, 6.579251212010101,
+        8.525161361065415, 10.60460290274525, 12.801827480081469
+    ];
+    if k < 10.0 {return TABLE[k as usize];}
+    let r = 1.0/k;
+    (k + 0.5)*f64::ln(k) - k + 0.9189385332046728 + r*(1.0/12.0 - r*r/360.0)
+}
+
+#[cfg(feature = "std")]
+/// The start of the tail of the normal Ziggurat.
+const ZIGGURAT_NORMAL_R: f64 = 3.654152885361009;
+
+#[cfg(feature = "std")]
+/// The layer boundaries `x` of the Ziggurat of the standard normal
+/// density up to normalization, `exp(-x^2/2)`, with 256 layers of area
+/// 0.00492867323399. Layer 0 is the base strip, including the tail
+/// beyond `x[1]`, of width `x[0]`. Layer `i` for `i > 0` spans
+/// `0..x[i]` with heights from `y[i]` to `y[i + 1]`. Checked against
+/// the recurrence by the test `ziggurat_normal_tables`.
+static ZIGGURAT_NORMAL_X: [f64; 257] = [
+    3.91075795953709, 3.654152885361009, 3.4492782985609645, 3.320244733839166,
+    3.224575052047029, 3.14788928951715, 3.083526132001233, 3.0278377917686354,
+    2.978603279880845, 2.9343668672078542, 2.894121053612348, 2.8571387308721325,
+    2.822877396825325, 2.7909211740007858, 2.7609440052788226, 2.732685359042827,
+    2.705933656121858, 2.680514643284522, 2.6562830375755024, 2.6331163936303246,
+    2.6109105184875485, 2.589575986706995, 2.5690354526805366, 2.5492215503234608,
+    2.530075232158517, 2.5115444416253423, 2.4935830412696807, 2.4761499396691433,
+    2.4592083743333113, 2.4427253181989568, 2.426670984935726, 2.4110184138996855,
+    2.3957431197804806, 2.380822795170626, 2.3662370567158186, 2.35196722737766,
+    2.3379961487950314, 2.324308018869623, 2.31088825059985, 2.2977233489013296,
+    2.284800802722946, 2.272108990226824, 2.259637095172218, 2.2473750329458078,
+    2.235313384928328, 2.2234433400909057, 2.2117566428825444, 2.200245546609648,
+    2.1889027716247207, 2.1777214677386416, 2.166695180352646, 2.1558178198750633,
+    2.1450836340462036, 2.13448718284432, 2.1240233156878157, 2.113687150684934,
+    2.103474055713147, 2.0933796311370503, 2.083399693996552, 2.0735302635169788,
+    2.0637675478099564, 2.054107931648865, 2.044547965215733, 2.0350843537278087,
+    2.025713947862033, 2.0164337349043717, 2.007240830558685, 1.9981324713565642,
+    1.9891060076155713, 1.9801588968985984, 1.9712886979317696, 1.962493064942462,
+    1.953769742382734, 1.945116560006754, 1.936531428273759, 1.9280123340507183,
+    1.9195573365912288, 1.9111645637692822, 1.9028322085484464, 1.89455852566871,
+    1.8863418285347764, 1.8781804862909777, 1.8700729210692368, 1.8620176053976323,
+    1.8540130597581481, 1.8460578502831198, 1.8381505865807286, 1.8302899196806666,
+    1.8224745400917832, 1.8147031759641676, 1.8069745913486934, 1.7992875845475802,
+    1.79164098655001, 1.7840336595472763, 1.776464495522345, 1.768932414909078,
+    1.7614363653167067, 1.753975320315455, 1.746548278279493, 1.739154261283669,
+    1.7317923140507072, 1.7244615029457757, 1.7171609150155407, 1.709889657069006,
+    1.702646854797614, 1.6954316519322385, 1.6882432094348587, 1.6810807047228233,
+    1.6739433309237604, 1.6668302961592867, 1.6597408228557895, 1.6526741470806485,
+    1.6456295179023603, 1.6386061967731111, 1.631603456932422, 1.6246205828305684,
+    1.6176568695705342, 1.6107116223673337, 1.603784156023583, 1.5968737944202613,
+    1.5899798700216485, 1.5831017233934714, 1.5762387027333329, 1.5693901634125345,
+    1.5625554675284397, 1.555733983466555, 1.5489250854715355, 1.5421281532263476,
+    1.5353425714388431, 1.5285677294350246, 1.521803020758293, 1.5150478427739924,
+    1.508301596278572, 1.5015636851127065, 1.4948335157777184, 1.4881104970546544,
+    1.4813940396253757, 1.4746835556950255, 1.467978458615231, 1.4612781625074078,
+    1.4545820818855233, 1.4478896312776697, 1.441200224845798, 1.4345132760029464,
+    1.4278281970272904, 1.4211443986723231, 1.4144612897724647, 1.4077782768433715,
+    1.4010947636762026, 1.3944101509250713, 1.3877238356868846, 1.381035211072742,
+    1.3743436657700305, 1.367648583594318, 1.3609493430301018, 1.3542453167594306,
+    1.3475358711773593, 1.3408203658931521, 1.3340981532160836, 1.3273685776246247,
+    1.32063097521773, 1.313884673146869, 1.3071289890273539, 1.3003632303274337,
+    1.2935866937335176, 1.2867986644897864, 1.2799984157103332, 1.2731852076618437,
+    1.2663582870146883, 1.2595168860601442, 1.2526602218912979, 1.245787495544998,
+    1.2388978911020274, 1.231990574742445, 1.225064693752808, 1.2181193754817266,
+    1.2111537262399112, 1.2041668301405601, 1.197157747875586, 1.1901255154228016,
+    1.1830691426787607, 1.1759876120114898, 1.1688798767268338, 1.1617448594415742,
+    1.1545814503558518, 1.1473885054167339, 1.1401648443639958, 1.132909248648337,
+    1.1256204592112944, 1.118297174115063, 1.1109380460092495, 1.1035416794202682,
+    1.0961066278476035, 1.0886313906495142, 1.0811144096988894, 1.0735540657878717,
+    1.0659486747575067, 1.0582964833260065, 1.0505956645862071, 1.0428443131393705,
+    1.0350404398286053, 1.0271819660307513, 1.0192667174605292, 1.0112924174349784,
+    1.0032566795395914, 0.9951569996299431, 0.9869907470938463, 0.9787551552889378,
+    0.9704473110588646, 0.9620641432176052, 0.9536024098755727, 0.9450586844625711,
+    0.9364293402808969, 0.9277105333962348, 0.918898183643735, 0.909987953490769,
+    0.9009752244551745, 0.8918550707267924, 0.8826222295789101, 0.8732710680824946,
+    0.8637955455468269, 0.8541891710015606, 0.8444449549024237, 0.8345553540795188,
+    0.8245122087452886, 0.8143066701280643, 0.8039291169826649, 0.7933690588331528,
+    0.7826150232995888, 0.7716544242167394, 0.7604734064220832, 0.7490566620095817,
+    0.7373872114258386, 0.7254461409013035, 0.7132122851820227, 0.7006618410975844,
+    0.6877678927862577, 0.6744998228274365, 0.660822574234206, 0.6466957148843889,
+    0.6320722363750246, 0.6168969899962355, 0.6011046177439404, 0.5846167660937223,
+    0.567338257040473, 0.5491517023130268, 0.5299097206464951, 0.5094233295859334,
+    0.48744396612175434, 0.46363433677176324, 0.43751840218666266, 0.40838913458800075,
+    0.3751213328504657, 0.33573751918045946, 0.2861745917472605, 0.2152418959132738,
+    0.0
+];
+
+#[cfg(feature = "std")]
+/// The heights `exp(-x^2/2)` at `ZIGGURAT_NORMAL_X`, and 1.0 at the top.
+static ZIGGURAT_NORMAL_Y: [f64; 257] = [
+    0.0004774677645866553, 0.001260285930498598, 0.002609072746106363, 0.0040379725933718715,
+    0.005522403299264754, 0.00705087547139211, 0.008616582769422917, 0.0102149714397311,
+    0.011842757857943104, 0.013497450601780807, 0.015177088307982072, 0.01688008315259584,
+    0.01860512127578335, 0.020351096230109354, 0.022117062707379922, 0.023902203305873237,
+    0.025705804008632656, 0.027527235669693315, 0.02936593975823011, 0.03122141719202369,
+    0.0330932194586887, 0.03498094146183307, 0.03688421568869115, 0.03880270740465692,
+    0.04073611065607875, 0.04268414491661938, 0.044646552251446536, 0.046623094902089664,
+    0.048613553216035145, 0.05061772386112179, 0.05263541827697365, 0.054666461325077916,
+    0.05671069010639947, 0.058767952921137984, 0.060838108349751806, 0.06292102443797785,
+    0.06501657797147044, 0.06712465382802399, 0.06924514439725027, 0.07137794905914197,
+    0.07352297371424099, 0.07568013035919496, 0.07784933670237221, 0.08003051581494751,
+    0.08222359581349568, 0.08442850957065466, 0.08664519445086778, 0.08887359206859423,
+    0.09111364806670073, 0.09336531191302662, 0.09562853671335333, 0.09790327903921563,
+    0.10018949876917202, 0.10248715894230627, 0.10479622562286706, 0.10711666777507288,
+    0.10944845714721002, 0.11179156816424558, 0.11414597782825521, 0.11651166562603701,
+    0.1188886134433457, 0.12127680548523544, 0.1236762282020514, 0.12608687022065035,
+    0.12850872228047364, 0.13094177717412817, 0.13338602969216284, 0.13584147657175735,
+    0.13830811644906432, 0.1407859498149683, 0.14327497897404712, 0.14577520800653793,
+    0.14828664273312872, 0.15080929068241017, 0.15334316106083767, 0.15588826472506456,
+    0.15844461415652022, 0.16101222343811766, 0.16359110823298295, 0.16618128576511007,
+    0.16878277480185033, 0.17139559563815562, 0.17401977008249936, 0.17665532144440665,
+    0.1793022745235304, 0.1819606556002165, 0.18463049242750454, 0.18731181422451693,
+    0.19000465167119307, 0.1927090369043288, 0.1954250035148856, 0.1981525865465381,
+    0.20089182249543133, 0.2036427493111215, 0.20640540639867933, 0.20917983462193565,
+    0.21196607630785294, 0.2147641752520085, 0.21757417672517837, 0.2203961274810116,
+    0.2232300757647896, 0.22607607132326488, 0.22893416541557748, 0.23180441082524852,
+    0.2346868618732527, 0.23758157443217368, 0.2404886059414491, 0.243408015423712,
+    0.24633986350223877, 0.2492842124195167, 0.25224112605694377, 0.25521066995567715,
+    0.258192911338648, 0.2611879191337637, 0.26419576399831757, 0.26721651834463184,
+    0.27025025636696, 0.2732970540696758, 0.27635698929678126, 0.2794301417627653,
+    0.2825165930848494, 0.2856164268166581, 0.28872972848335393, 0.291856585618281,
+    0.29499708780116257, 0.29815132669790134, 0.3013193961020341, 0.3045013919778963,
+    0.30769741250555377, 0.3109075581275637, 0.31413193159763014, 0.3173706380312224,
+    0.32062378495823013, 0.323891482377732, 0.3271738428149586, 0.3304709813805371,
+    0.3337830158321085, 0.3371100666384128, 0.34045225704594545, 0.34380971314829134,
+    0.3471825639582515, 0.3505709414828812, 0.35397498080156925, 0.3573948201472905,
+    0.36083060099117575, 0.3642824681305496, 0.3677505697805962, 0.37123505766982134,
+    0.3747360871394914, 0.3782538172472381, 0.38178841087503135, 0.38534003484173396,
+    0.3889088600204646, 0.39249506146101076, 0.3960988185175471, 0.39972031498193167,
+    0.4033597392228689, 0.40701728433124795, 0.4106931482719832, 0.4143875340427068,
+    0.4181006498396846, 0.4218327092313533, 0.4255839313399006, 0.4293545410313415,
+    0.43314476911457406, 0.4369548525499293, 0.4407850346677699, 0.44463556539772775,
+    0.44850670150921407, 0.4523987068638825, 0.45631185268077357, 0.4602464178149235,
+    0.46420268905027884, 0.46818096140782217, 0.47218153846988326, 0.4762047327216838,
+    0.4802508659112497, 0.4843202694289116, 0.48841328470771206, 0.49253026364614866,
+    0.4966715690547963, 0.5008375751284821, 0.5050286679458288, 0.5092452459981361,
+    0.513487720749743, 0.5177565172322006, 0.5220520746747949, 0.5263748471741867,
+    0.5307253044061939, 0.5351039323830196, 0.5395112342595446, 0.5439477311926499,
+    0.5484139632579211, 0.5529104904285199, 0.5574378936214863, 0.5619967758172779,
+    0.5665877632589518, 0.571211506738075, 0.5758686829752105, 0.5805599961036835,
+    0.5852861792663003, 0.590047996335792, 0.5948462437709913, 0.5996817526221677,
+    0.6045553907005495, 0.6094680649288954, 0.6144207238920768, 0.6194143606090392,
+    0.6244500155502742, 0.6295287799281283, 0.63465179929096, 0.639820277456439,
+    0.6450354808242519, 0.6502987431142946, 0.6556114705832247, 0.6609751477802414,
+    0.6663913439123806, 0.6718617199007664, 0.6773880362225131, 0.6829721616487914,
+    0.6886160830085271, 0.6943219161300326, 0.7000919181404901, 0.7059285013367974,
+    0.7118342488823585, 0.7178119326349014, 0.7238645334728816, 0.7299952645658024,
+    0.7362075981312667, 0.7425052963446362, 0.7488924472237267, 0.7553735065117545,
+    0.7619533468415465, 0.7686373158033348, 0.7754313049861383, 0.7823418326598619,
+    0.7893761435711986, 0.7965423304282546, 0.8038494831763895, 0.8113078743182199,
+    0.8189291916094148, 0.8267268339520942, 0.8347162929929304, 0.8429156531184411,
+    0.8513462584651237, 0.8600336212030086, 0.8690086880437932, 0.8783096558161468,
+    0.8879846607633999, 0.898095921906304, 0.9087264400605629, 0.9199915050483602,
+    0.9320600759689902, 0.945198953453078, 0.9598790918124159, 0.9771017012827313,
+    1.0
+];
+
+#[cfg(feature = "std")]
+/// The start of the tail of the exponential Ziggurat.
+const ZIGGURAT_EXP_R: f64 = 7.69711747013105;
+
+#[cfg(feature = "std")]
+/// The layer boundaries of the Ziggurat of the standard exponential
+/// density `exp(-x)` with 256 layers of area 0.003949659822581557,
+/// laid out as `ZIGGURAT_NORMAL_X`. Checked against the recurrence by
+/// the test `ziggurat_exp_tables`.
+static ZIGGURAT_EXP_X: [f64; 257] = [
+    8.697117470131053, 7.69711747013105, 6.941033629377213, 6.47837849383257,
+    6.144164665772473, 5.8821443157954, 5.666410167454034, 5.4828906275260625,
+    5.323090505754398, 5.1814872813015, 5.054288489981304, 4.9387770859012505,
+    4.832939741025112, 4.735242996601741, 4.644491885420085, 4.559737061707351,
+    4.480211746528422, 4.405287693473573, 4.334443680317273, 4.267242480277366,
+    4.203313713735184, 4.1423408656640515, 4.084051310408298, 4.028208544647937,
+    3.974606066673789, 3.9230625001354897, 3.873417670399509, 3.8255294185223367,
+    3.779270992411668, 3.7345288940397974, 3.691201090237419, 3.6491955157608538,
+    3.6084288131289095, 3.568825265648337, 3.5303158891293434, 3.4928376547740596,
+    3.45633282113276, 3.42074835725112, 3.386035442460301, 3.3521490309001094,
+    3.319047470970748, 3.2866921715990687, 3.25504730857045, 3.224079565286264,
+    3.1937579032122403, 3.164053358025973, 3.1349388580844404, 3.1063890623398245,
+    3.0783802152540902, 3.050890016615455, 3.0238975044556766, 2.9973829495161306,
+    2.9713277599210897, 2.9457143948950457, 2.920526286512741, 2.895747768600142,
+    2.8713640120155364, 2.847360965635189, 2.8237253024500353, 2.800444370250738,
+    2.7775061464397566, 2.7548991965623446, 2.7326126361947, 2.7106360958679288,
+    2.6889596887418037, 2.6675739807732666, 2.646469963151809, 2.6256390267977885,
+    2.6050729387408356, 2.5847638202141408, 2.5647041263169053, 2.54488662711187,
+    2.525304390037828, 2.505950763528594, 2.4868193617402095, 2.467904050297365,
+    2.4491989329782498, 2.4306983392644197, 2.4123968126888706, 2.394289099921458,
+    2.3763701405361406, 2.3586350574093373, 2.3410791477030344, 2.3236978743901964,
+    2.30648685828358, 2.2894418705322694, 2.272558825553155, 2.255833774367219,
+    2.239262898312909, 2.222842503111037, 2.206569013257664, 2.19043896672322,
+    2.1744490099377747, 2.158595893043886, 2.142876465399842, 2.1272876713173683,
+    2.111826546019042, 2.096490211801715, 2.081275874393225, 2.0661808194905755,
+    2.051202409468585, 2.0363380802487696, 2.021585338318926, 2.0069417578945186,
+    1.9924049782135766, 1.9779727009573604, 1.9636426877895483, 1.949412758007185,
+    1.9352807862970514, 1.921244700591528, 1.9073024800183875, 1.8934521529393082,
+    1.8796917950722112, 1.866019527692828, 1.8524335159111756, 1.83893196701888,
+    1.8255131289035198, 1.8121752885263906, 1.7989167704602909, 1.785735935484126,
+    1.7726311792313056, 1.7596009308890748, 1.7466436519460744, 1.7337578349855716,
+    1.7209420025219353, 1.7081947058780578, 1.695514524101538, 1.682900062917554,
+    1.6703499537164521, 1.6578628525741728, 1.6454374393037237, 1.6330724165359913,
+    1.620766508828258, 1.6085184617988584, 1.5963270412864834, 1.584191032532689,
+    1.5721092393862297, 1.560080483527888, 1.5481036037145135, 1.536177455041032,
+    1.5243009082192263, 1.512472848872117, 1.5006921768428167, 1.488957805516746,
+    1.4772686611561339, 1.4656236822457454, 1.4540218188487934, 1.4424620319720125,
+    1.4309432929388797, 1.4194645827699832, 1.4080248915695357, 1.3966232179170421,
+    1.385258568263122, 1.3739299563284906, 1.3626364025050868, 1.3513769332583352,
+    1.3401505805295046, 1.3289563811371166, 1.3177933761763247, 1.3066606104151741,
+    1.295557131686601, 1.2844819902750126, 1.2734342382962411, 1.2624129290696153,
+    1.2514171164808525, 1.2404458543344066, 1.229498195693849, 1.2185731922087901,
+    1.2076698934267611, 1.196787346088403, 1.1859245934042022, 1.1750806743109117,
+    1.164254622705679, 1.1534454666557747, 1.1426522275816728, 1.1318739194110785,
+    1.1211095477013302, 1.110358108727411, 1.0996185885325973, 1.0888899619385468,
+    1.0781711915113723, 1.0674612264799677, 1.0567590016025514, 1.0460634359770442,
+    1.0353734317905285, 1.0246878730026172, 1.0140056239570965, 1.0033255279156967,
+    0.9926464055072759, 0.9819670530850626, 0.9712862409839033, 0.9606027116686665,
+    0.949915177764076, 0.9392223199552623, 0.9285227847472104, 0.9178151820700443,
+    0.9070980827156903, 0.8963700155898899, 0.8856294647617515, 0.8748748662910251,
+    0.8641046048110045, 0.8533170098423734, 0.8425103518103685, 0.8316828377342732,
+    0.8208326065544118, 0.8099577240574183, 0.7990561773554872, 0.7881258688694924,
+    0.7771646097591297, 0.7661701127354347, 0.7551399841819822, 0.7440717155005081,
+    0.7329626735843654, 0.7218100903087562, 0.710611050909655, 0.699362481103232,
+    0.6880611327737478, 0.6767035680295226, 0.6652861413926779, 0.653804979847665,
+    0.6422559604245364, 0.6306346849334903, 0.6189364513948761, 0.6071562216203,
+    0.5952885842915029, 0.5833277127487695, 0.5712673165325883, 0.5591005855115406,
+    0.5468201251633106, 0.5344178812371656, 0.521885051592135, 0.5092119824436544,
+    0.49638804551867116, 0.48340149165346186, 0.470239275082169, 0.45688684093142024,
+    0.4433278660735524, 0.4295439402254107, 0.41551416960035636, 0.40121467889627777,
+    0.3866179779411196, 0.37169214532991723, 0.3563997602583938, 0.3406964810648491,
+    0.32452911701690945, 0.30783295467493216, 0.2905279554912304, 0.2725131854784647,
+    0.253658363385912, 0.23379048305967473, 0.21267151063096662, 0.18995868962243184,
+    0.16512762256418728, 0.1373049809400126, 0.10483850756581878, 0.06385216381500157,
+    0.0
+];
+
+#[cfg(feature = "std")]
+/// The heights `exp(-x)` at `ZIGGURAT_EXP_X`, and 1.0 at the top.
+static ZIGGURAT_EXP_Y: [f64; 257] = [
+    0.00016706669230796337, 0.0004541343538414966, 0.0009672692823271743, 0.0015362997803015726,
+    0.002145967743718907, 0.0027887987935740757, 0.003460264777836904, 0.004157295120833797,
+    0.004877655983542396, 0.005619642207205489, 0.006381905937319183, 0.007163353183634991,
+    0.007963077438017043, 0.008780314985808977, 0.009614413642502212, 0.01046481018102998,
+    0.0113310135978346, 0.012212592426255378, 0.013109164931254991, 0.014020391403181943,
+    0.014945968011691148, 0.015885621839973156, 0.01683910682603994, 0.017806200410911355,
+    0.018786700744696024, 0.01978042433800974, 0.020787204072578114, 0.02180688750428358,
+    0.02283933540638524, 0.023884420511558174, 0.024942026419731787, 0.02601204664513422,
+    0.027094383780955803, 0.028188948763978646, 0.02929566022463741, 0.03041444391046662,
+    0.03154523217289362, 0.032687963508959555, 0.03384258215087436, 0.03500903769739743,
+    0.03618728478193144, 0.03737728277295938, 0.03857899550307487, 0.03979239102337414,
+    0.04101744138041484, 0.042254122413316254, 0.0435024135688882, 0.04476229773294329,
+    0.046033761076175184, 0.04731679291318156, 0.048611385573379504, 0.04991753428270638,
+    0.05123523705512628, 0.052564494593071685, 0.05390531019604608, 0.05525768967669703,
+    0.05662164128374287, 0.05799717563120066, 0.05938430563342028, 0.06078304644547966,
+    0.062193415408541036, 0.06361543199980738, 0.0650491177867538, 0.06649449638533982,
+    0.06795159342193664, 0.06942043649872878, 0.07090105516237184, 0.07239348087570875,
+    0.07389774699236475, 0.07541388873405841, 0.07694194317048052, 0.07848194920160644,
+    0.0800339475423199, 0.08159798070923742, 0.0831740930096324, 0.08476233053236815,
+    0.08636274114075693, 0.08797537446727023, 0.08960028191003289, 0.0912375166310402,
+    0.09288713355604357, 0.09454918937605587, 0.09622374255043283, 0.09791085331149221,
+    0.09961058367063713, 0.10132299742595363, 0.1030481601712577, 0.10478613930657016,
+    0.10653700405000163, 0.10830082545103376, 0.11007767640518536, 0.11186763167005628,
+    0.11367076788274429, 0.1154871635786335, 0.11731689921155553, 0.11916005717532764,
+    0.12101672182667479, 0.12288697950954511, 0.12477091858083093, 0.12666862943751067,
+    0.1285802045452282, 0.13050573846833077, 0.1324453279013875, 0.1343990717022136,
+    0.13636707092642883, 0.13834942886358018, 0.1403462510748624, 0.14235764543247215,
+    0.14438372216063472, 0.1464245938783449, 0.14848037564386674, 0.15055118500103984,
+    0.1526371420274428, 0.15473836938446803, 0.15685499236936515, 0.15898713896931413,
+    0.16113493991759195, 0.16329852875190173, 0.16547804187493592, 0.16767361861725008,
+    0.16988540130252755, 0.17211353531531998, 0.1743581691713534, 0.17661945459049483,
+    0.17889754657247828, 0.18119260347549626, 0.18350478709776744, 0.18583426276219708,
+    0.18818119940425426, 0.19054576966319536, 0.1929281499767713, 0.1953285206795632,
+    0.19774706610509882, 0.2001839746919112, 0.20263943909370896, 0.20511365629383765,
+    0.20760682772422198, 0.21011915938898823, 0.21265086199297822, 0.21520215107537863,
+    0.21777324714870047, 0.22036437584335944, 0.2229757680581201, 0.22560766011668396,
+    0.22826029393071662, 0.23093391716962736, 0.2336287834374333, 0.23634515245705956,
+    0.2390832902624491, 0.24184346939887713, 0.24462596913189202, 0.24743107566532754,
+    0.25025908236886224, 0.2531102900156294, 0.2559850070304153, 0.2588835497490162,
+    0.2618062426893629, 0.26475341883506215, 0.26772541993204474, 0.27072259679905997,
+    0.2737453096528029, 0.2767939284485173, 0.27986883323697287, 0.28297041453878075,
+    0.2860990737370768, 0.2892552234896777, 0.29243928816189263, 0.29565170428126125,
+    0.29889292101558185, 0.3021634006756935, 0.30546361924459026, 0.3087940669345602,
+    0.3121552487741796, 0.31554768522712895, 0.31897191284495724, 0.3224284849560892,
+    0.32591797239355635, 0.32944096426413644, 0.3329980687618091, 0.3365899140286777,
+    0.3402171490667802, 0.3438804447045026, 0.34758049462163715, 0.35131801643748345,
+    0.3550937528667876, 0.35890847294875, 0.362762973354818, 0.3666580797815144,
+    0.3705946484351462, 0.3745735676159024, 0.37859575940958107, 0.38266218149601006,
+    0.38677382908413793, 0.3909317369847974, 0.39513698183329043, 0.39939068447523135,
+    0.40369401253053055, 0.4080481831520327, 0.41245446599716146, 0.4169141864330032,
+    0.4214287289976169, 0.4259995411430347, 0.43062813728845917, 0.4353161032156369,
+    0.4400651008423542, 0.44487687341454885, 0.44975325116275533, 0.45469615747461584,
+    0.459707615642138, 0.4647897562504265, 0.4699448252839603, 0.4751751930373777,
+    0.48048336393045454, 0.48587198734188525, 0.49134386959403287, 0.4969019872415499,
+    0.5025495018413481, 0.5082897764106432, 0.5141263938147489, 0.5200631773682339,
+    0.5261042139836201, 0.5322538802630437, 0.5385168720028622, 0.5448982376724401,
+    0.5514034165406417, 0.5580382822625879, 0.5648091929124006, 0.5717230486648262,
+    0.5787873586028454, 0.5860103184772684, 0.5934009016917338, 0.6009689663652326,
+    0.6087253820796223, 0.6166821809152079, 0.6248527387036662, 0.6332519942143664,
+    0.6418967164272664, 0.6508058334145714, 0.6600008410790001, 0.6695063167319252,
+    0.6793505722647658, 0.6895664961170784, 0.7001926550827886, 0.7112747608050765,
+    0.7228676595935725, 0.735038092431424, 0.7478686219851957, 0.7614633888498968,
+    0.7759568520401162, 0.7915276369724963, 0.808421651523009, 0.8269932966430511,
+    0.8477855006239905, 0.8717043323812047, 0.9004699299257477, 0.9381436808621765,
+    1.0
+];
+
+/// Evaluating `NonEmpty::<N>::OK` fails to compile if `N == 0`.
+/// An inline `const` block would need Rust 1.79.
+struct NonEmpty<const N: usize>;
+
+impl<const N: usize> NonEmpty<N> {
+    const OK: () = assert!(N > 0, "choice_array: empty array");
+}
+
+/// The first index at which the running sum of the weights exceeds
+/// `x`, for `x` uniform in `[0, total)`. The weights are checked by
+/// the caller to be finite, non-negative and of positive sum.
+fn weighted_index<I: Iterator<Item = f64>>(x: f64, weights: I) -> usize {
+    let mut acc = 0.0;
+    let mut last = 0;
+    for (i, w) in weights.enumerate() {
+        acc += w;
+        if x < acc {return i;}
+        if w > 0.0 {last = i;}
+    }
+    // Reached only by rounding, take the last possible index.
+    last
+}
+
+/// Calls `f` on each byte of the buffer with the next byte of the
+/// stream `fill` writes: the bytes of `rand_u32`, lowest first.
+fn for_each_random_byte<R, F>(rng: &mut R, a: &mut [u8], mut f: F)
+where R: Rand, F: FnMut(&mut u8, u8)
+{
+    let mut x = rng.rand_u32();
+    let mut count = 3;
+    for p in a {
+        f(p, x as u8);
+        if count == 0 {
+            x = rng.rand_u32();
+            count = 3;
+        } else {
+            x >>= 8;
+            count -= 1;
+        }
+    }
+}
+
+/// The output function of SplitMix64, a bijective mixing of bits.
+const fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(feature = "std")]
+/// The indices of the values, sorted by decreasing value, ties by
+/// increasing index. The values must not contain NaN.
+fn descending_order(values: &[f64]) -> alloc::vec::Vec<usize> {
+    let mut order: alloc::vec::Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&i, &j| values[j].partial_cmp(&values[i]).unwrap());
+    order
+}
+
+/// The largest float below the finite `x`.
+fn prev_float(x: f64) -> f64 {
+    if x > 0.0 {
+        f64::from_bits(x.to_bits() - 1)
+    } else if x == 0.0 {
+        -f64::from_bits(1)
+    } else {
+        f64::from_bits(x.to_bits() + 1)
+    }
+}
+
+/// Rejects bounds that would propagate NaN or infinity.
+fn check_float_range(name: &str, a: f64, b: f64) {
+    assert!(a.is_finite() && b.is_finite(),
+        "{}: bounds must be finite, got {} and {}", name, a, b);
+    assert!((b - a).is_finite(), "{}: width of range overflows", name);
+}
+
 /** Provided utilities.
 
 This interface permits to hide the used engine:
@@ -61,14 +460,19 @@ pub trait Rand: Sized {
     fn from_seed(seed: u64) -> Self;
 
     #[cfg(feature = "std")]
-    /// Use system time as micro seconds as the seed.
+    /// Use system time as micro seconds as the seed. The time is mixed
+    /// with a process-wide counter by SplitMix64, so that generators
+    /// created in quick succession still diverge strongly.
     /// Needs feature `std` to be enabled.
     fn from_time() -> Self {
         use std::time::SystemTime;
+        use core::sync::atomic::{AtomicU64, Ordering};
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
         let time_seed = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or_else(|e| e.duration()).as_micros() as u64;
-        Self::from_seed(time_seed)
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        Self::from_seed(mix_seed(time_seed, count))
     }
 
     /// A sample from the uniform distribution on `0..=u32::MAX`.
@@ -92,8 +496,16 @@ pub trait Rand: Sized {
         (self.rand_u32() as u64) << 32 | (self.rand_u32() as u64)
     }
 
+    /// Like `rand_u64`, but the output is passed through the
+    /// SplitMix64 finalizer. Slower, but all bits, the low-order
+    /// ones in particular, are of higher quality.
+    #[inline]
+    fn rand_u64_scrambled(&mut self) -> u64 {
+        splitmix64(self.rand_u64())
+    }
+
     /// A sample from the uniform distribution on `0..=usize::MAX`.
-    #[cfg(target_pointer_width = "32")]
+    #[cfg(any(target_pointer_width = "16", target_pointer_width = "32"))]
     #[inline]
     fn rand_usize(&mut self) -> usize {
         self.rand_u32() as usize
@@ -106,32 +518,127 @@ pub trait Rand: Sized {
         self.rand_u64() as usize
     }
 
+    /// A sample from the uniform distribution on `1..=u32::MAX`.
+    /// Zero is rejected.
+    fn rand_nonzero_u32(&mut self) -> core::num::NonZeroU32 {
+        loop {
+            if let Some(x) = core::num::NonZeroU32::new(self.rand_u32()) {
+                return x;
+            }
+        }
+    }
+
+    /// A sample from the uniform distribution on `1..=u64::MAX`.
+    /// Zero is rejected.
+    fn rand_nonzero_u64(&mut self) -> core::num::NonZeroU64 {
+        loop {
+            if let Some(x) = core::num::NonZeroU64::new(self.rand_u64()) {
+                return x;
+            }
+        }
+    }
+
+    /// Either `1` or `-1`, each with probability 1/2.
+    #[inline]
+    fn rand_sign(&mut self) -> i32 {
+        if self.rand_u32() >> 31 == 0 {1} else {-1}
+    }
+
+    /// `Less`, `Equal` or `Greater`, each with probability 1/3.
+    fn rand_ordering(&mut self) -> core::cmp::Ordering {
+        use core::cmp::Ordering;
+        [Ordering::Less, Ordering::Equal, Ordering::Greater][self.rand_bounded_u32(3) as usize]
+    }
+
+    /// `Less`, `Equal` or `Greater` with probabilities proportional to
+    /// `lt`, `eq` and `gt`, as for `rand_index_weighted`.
+    fn rand_ordering_weighted(&mut self, lt: f64, eq: f64, gt: f64) -> core::cmp::Ordering {
+        use core::cmp::Ordering;
+        [Ordering::Less, Ordering::Equal, Ordering::Greater][self.rand_index_weighted(&[lt, eq, gt])]
+    }
+
     /// A sample from the uniform distribution on `0..m`.
     // Applies the idea of rejection sampling.
     fn rand_bounded_u32(&mut self, m: u32) -> u32 {
+        if m.is_power_of_two() {
+            // No rejection is possible.
+            return self.rand_u32() & (m - 1);
+        }
         let mask = wrapping_next_power_of_two_u32(m).wrapping_sub(1);
         loop {
             let x = mask & self.rand_u32();
             if x < m {return x;}
+            #[cfg(feature = "stats")]
+            self.record_rejection();
+        }
+    }
+
+    /// Like `rand_bounded_u32`, but also returns the number of
+    /// `rand_u32` draws consumed. The value and the stream position
+    /// afterwards are the same as for `rand_bounded_u32`.
+    fn rand_bounded_u32_counted(&mut self, m: u32) -> (u32, u32) {
+        if m.is_power_of_two() {
+            return (self.rand_u32() & (m - 1), 1);
+        }
+        let mask = wrapping_next_power_of_two_u32(m).wrapping_sub(1);
+        let mut count = 0;
+        loop {
+            let x = mask & self.rand_u32();
+            count += 1;
+            if x < m {return (x, count);}
+            #[cfg(feature = "stats")]
+            self.record_rejection();
+        }
+    }
+
+    /// Like `rand_bounded_u32`, but gives up and returns `None` if
+    /// all of `max_tries` draws are rejected. Each draw is accepted
+    /// with probability above 1/2, so for a sound generator `None`
+    /// is practically impossible for a moderate `max_tries`. This
+    /// guards against a broken `Rand` implementation.
+    fn rand_bounded_u32_capped(&mut self, m: u32, max_tries: u32) -> Option<u32> {
+        let mask = wrapping_next_power_of_two_u32(m).wrapping_sub(1);
+        for _ in 0..max_tries {
+            let x = mask & self.rand_u32();
+            if x < m {return Some(x);}
+            #[cfg(feature = "stats")]
+            self.record_rejection();
         }
+        None
     }
 
     /// A sample from the uniform distribution on `0..m`.
+    /// A draw is masked to the next power of two `p >= m` and rejected
+    /// if not below `m`, which happens with probability `1 - m/p < 1/2`.
+    /// See `expected_rejections` for the cost of a given bound.
     fn rand_bounded_u64(&mut self, m: u64) -> u64 {
         let mask = wrapping_next_power_of_two_u64(m).wrapping_sub(1);
         loop {
             let x = mask & self.rand_u64();
             if x < m {return x;}
+            #[cfg(feature = "stats")]
+            self.record_rejection();
         }
     }
 
+    #[cfg(feature = "stats")]
+    #[doc(hidden)]
+    /// Called for each rejected draw in `rand_bounded_u32`,
+    /// `rand_bounded_u64` and `UniformInt::sample`.
+    /// Needs feature `stats` to be enabled.
+    #[inline]
+    fn record_rejection(&mut self) {}
+
     /// A sample from the uniform distribution on `0..m`.
-    #[cfg(target_pointer_width = "32")]
+    // On these targets `usize` is at most 32 bits wide, so the cast
+    // is lossless.
+    #[cfg(any(target_pointer_width = "16", target_pointer_width = "32"))]
     fn rand_bounded_usize(&mut self, m: usize) -> usize {
         self.rand_bounded_u32(m as u32) as usize
     }
 
     /// A sample from the uniform distribution on `0..m`.
+    // The full 64-bit span is used, values above `u32::MAX` included.
     #[cfg(target_pointer_width = "64")]
     fn rand_bounded_usize(&mut self, m: usize) -> usize {
         self.rand_bounded_u64(m as u64) as usize
@@ -149,24 +656,192 @@ pub trait Rand: Sized {
 
     /// A sample from the uniform distribution on `a..b`.
     fn rand_range_i32(&mut self, a: i32, b: i32) -> i32 {
-        a + self.rand_bounded_u32((b - a) as u32) as i32
+        // The width b - a may exceed i32::MAX, but always fits in u32.
+        let m = (b as i64 - a as i64) as u32;
+        (a as i64 + self.rand_bounded_u32(m) as i64) as i32
     }
 
     /// A sample from the uniform distribution on `a..b`.
     fn rand_range_i64(&mut self, a: i64, b: i64) -> i64 {
-        a + self.rand_bounded_u64((b - a) as u64) as i64
+        // The width b - a may exceed i64::MAX, but always fits in u64.
+        let m = (b as i128 - a as i128) as u64;
+        (a as i128 + self.rand_bounded_u64(m) as i128) as i64
+    }
+
+    /// A sample from the uniform distribution on `a..=b`.
+    /// Needs `a <= b`, the full range `0..=u32::MAX` is allowed.
+    fn rand_range_inclusive_u32(&mut self, a: u32, b: u32) -> u32 {
+        assert!(a <= b, "rand_range_inclusive_u32: needs a <= b");
+        match (b - a).checked_add(1) {
+            Some(m) => a + self.rand_bounded_u32(m),
+            None => self.rand_u32()
+        }
+    }
+
+    /// A sample from the uniform distribution on `a..=b`.
+    /// Needs `a <= b`, the full range `0..=u64::MAX` is allowed.
+    fn rand_range_inclusive_u64(&mut self, a: u64, b: u64) -> u64 {
+        assert!(a <= b, "rand_range_inclusive_u64: needs a <= b");
+        match (b - a).checked_add(1) {
+            Some(m) => a + self.rand_bounded_u64(m),
+            None => self.rand_u64()
+        }
+    }
+
+    /// A sample from the uniform distribution on `a..=b`.
+    /// Needs `a <= b`.
+    fn rand_range_inclusive_i32(&mut self, a: i32, b: i32) -> i32 {
+        assert!(a <= b, "rand_range_inclusive_i32: needs a <= b");
+        // Flipping the sign bit maps i32 monotonically onto u32.
+        let x = self.rand_range_inclusive_u32(a as u32 ^ 1 << 31, b as u32 ^ 1 << 31);
+        (x ^ 1 << 31) as i32
+    }
+
+    /// A sample from the uniform distribution on `a..=b`.
+    /// Needs `a <= b`.
+    fn rand_range_inclusive_i64(&mut self, a: i64, b: i64) -> i64 {
+        assert!(a <= b, "rand_range_inclusive_i64: needs a <= b");
+        // Flipping the sign bit maps i64 monotonically onto u64.
+        let x = self.rand_range_inclusive_u64(a as u64 ^ 1 << 63, b as u64 ^ 1 << 63);
+        (x ^ 1 << 63) as i64
+    }
+
+    /// A sample from the uniform distribution on an integer range,
+    /// like `a..b` or `a..=b`, see `SampleRange` and the macro `rand!`.
+    fn sample_range<T, S: SampleRange<T>>(&mut self, range: S) -> T
+    where Self: Sized
+    {
+        range.sample(self)
+    }
+
+    /// A retry delay by exponential backoff with full jitter: a sample
+    /// from the uniform distribution on `0..=min(cap_ms, base_ms*2^attempt)`.
+    /// The exponential saturates instead of overflowing.
+    fn full_jitter(&mut self, base_ms: u64, cap_ms: u64, attempt: u32) -> u64 {
+        let factor = 1_u64.checked_shl(attempt).unwrap_or(u64::MAX);
+        let ceiling = base_ms.saturating_mul(factor).min(cap_ms);
+        self.rand_range_inclusive_u64(0, ceiling)
+    }
+
+    /// A random position on a ring of size `modulus`, within `radius`
+    /// steps of `center` in either direction: `(center + offset) % modulus`
+    /// with `offset` uniform on `-radius..=radius`. If the window wraps
+    /// onto itself, that is `2*radius + 1 > modulus`, some positions are
+    /// counted twice and thus more likely. Needs `modulus > 0` and
+    /// `radius <= usize::MAX/2`.
+    fn rand_near_modular(&mut self, center: usize, radius: usize, modulus: usize) -> usize {
+        assert!(modulus > 0, "rand_near_modular: modulus must be positive");
+        assert!(radius <= usize::MAX/2, "rand_near_modular: radius too large");
+        let m = modulus as u64;
+        let k = self.rand_range_inclusive_u64(0, 2*radius as u64) % m;
+        // In u128, so that nothing overflows.
+        let x = (center as u64 % m) as u128 + m as u128 - (radius as u64 % m) as u128 + k as u128;
+        (x % m as u128) as usize
+    }
+
+    /// A sample from the uniform distribution on `a..b`.
+    #[cfg(any(target_pointer_width = "16", target_pointer_width = "32"))]
+    fn rand_range_isize(&mut self, a: isize, b: isize) -> isize {
+        self.rand_range_i32(a as i32, b as i32) as isize
+    }
+
+    /// A sample from the uniform distribution on `a..b`.
+    #[cfg(target_pointer_width = "64")]
+    fn rand_range_isize(&mut self, a: isize, b: isize) -> isize {
+        self.rand_range_i64(a as i64, b as i64) as isize
+    }
+
+    /// A sample from the uniform distribution on the arithmetic
+    /// progression `a, a + step, a + 2*step, ...` below `b`.
+    /// Needs `step > 0` and `a < b`.
+    fn rand_range_step_u32(&mut self, a: u32, b: u32, step: u32) -> u32 {
+        assert!(step > 0, "rand_range_step_u32: step must be positive");
+        assert!(a < b, "rand_range_step_u32: empty range");
+        // Number of steps, (b - a + step - 1)/step without overflow.
+        let n = (b - a - 1)/step + 1;
+        a + self.rand_bounded_u32(n)*step
     }
 
     /// A sample from the uniform distribution on the interval [0, 1).
+    // The high 24 bits fit into the mantissa, thus no rounding takes
+    // place and the result is at most 1 - 2^-24.
     fn rand_f32(&mut self) -> f32 {
-        self.rand_u32() as f32 * 2.3283064E-10
+        (self.rand_u32() >> 8) as f32*(1.0/16777216.0)
     }
 
     /// A sample from the uniform distribution on the interval [0, 1).
+    /// The value is a multiple of 2^-32, at most 1 - 2^-32.
+    // The constant is exactly 2^-32 and every u32 is exactly
+    // representable, thus the product is exact.
     fn rand_f64(&mut self) -> f64 {
         self.rand_u32() as f64 * 2.3283064365386963E-10
     }
 
+    /// A sample from the uniform distribution on the closed interval
+    /// [0, 1]. The value is a multiple of 1/(2^53 - 1), and 1.0 is
+    /// returned if all of the 53 drawn bits are set.
+    fn rand_f64_closed(&mut self) -> f64 {
+        (self.rand_u64() >> 11) as f64*(1.0/9007199254740991.0)
+    }
+
+    /// A sample from the uniform distribution on the interval [a, b).
+    /// If rounding yields `b`, the largest float below `b` is taken.
+    /// Panics if `a` or `b` is NaN or infinite, if `a >= b`, or if
+    /// the width `b - a` overflows.
+    fn rand_range_f64(&mut self, a: f64, b: f64) -> f64 {
+        check_float_range("rand_range_f64", a, b);
+        assert!(a < b, "rand_range_f64: needs a < b");
+        let x = a + (b - a)*self.rand_f64();
+        if x < b {x} else {prev_float(b)}
+    }
+
+    /// A sample from the uniform distribution on the closed interval
+    /// [a, b]. Based on `rand_f64_closed`, the endpoint `a` is returned
+    /// exactly if its sample is 0.0 and `b` exactly if it is 1.0.
+    /// Rounding may also yield an endpoint from a nearby sample, but
+    /// never a value outside of [a, b].
+    /// Panics if `a` or `b` is NaN or infinite, if `a > b`, or if
+    /// the width `b - a` overflows.
+    fn rand_range_inclusive_f64(&mut self, a: f64, b: f64) -> f64 {
+        check_float_range("rand_range_inclusive_f64", a, b);
+        assert!(a <= b, "rand_range_inclusive_f64: needs a <= b");
+        let u = self.rand_f64_closed();
+        (a*(1.0 - u) + b*u).clamp(a, b)
+    }
+
+    /// A pair `(u, 1 - u)` of antithetic variates, where `u` is a sample
+    /// from the uniform distribution on the interval [0, 1).
+    ///
+    /// Each value on its own is uniformly distributed, but the two are
+    /// negatively correlated. For a monotone integrand `f`, the estimator
+    /// `(f(u) + f(1 - u))/2` thus has a smaller variance than the mean
+    /// of two independent evaluations, which speeds up Monte Carlo
+    /// integration. Here `1 - u` is computed exactly.
+    fn rand_f64_antithetic(&mut self) -> (f64, f64) {
+        let u = self.rand_f64();
+        (u, 1.0 - u)
+    }
+
+    /// A random color, uniformly distributed on all RGB triples.
+    fn rand_rgb(&mut self) -> (u8, u8, u8) {
+        let x = self.rand_u32();
+        (x as u8, (x >> 8) as u8, (x >> 16) as u8)
+    }
+
+    /// A random but visually pleasing color as an HSV triple. The hue is
+    /// uniformly distributed on [0, 1), the saturation on [0.5, 0.8)
+    /// and the value on [0.85, 1), which avoids dull and dark colors.
+    ///
+    /// To obtain a sequence of well distinguishable colors, take the
+    /// hue of the first color only and add the golden ratio conjugate
+    /// 0.618033988749895 modulo 1 for each further color.
+    fn rand_hsv_pleasing(&mut self) -> (f64, f64, f64) {
+        let h = self.rand_f64();
+        let s = 0.5 + 0.3*self.rand_f64();
+        let v = 0.85 + 0.15*self.rand_f64();
+        (h, s, v)
+    }
+
     #[cfg(feature = "std")]
     /// A sample from the normal distribution with mean `mu` and
     /// standard deviation `sigma`.
@@ -181,151 +856,4142 @@ pub trait Rand: Sized {
             }
         }
     }
-    
-    /// A sample from the uniform distribution on the non-empty slice.
-    fn choice<'a, T>(&mut self, a: &'a [T]) -> &'a T {
-        &a[self.rand_bounded_usize(a.len())]
+
+    #[cfg(feature = "std")]
+    /// A sample from the log-uniform distribution on [lo, hi), that is,
+    /// `ln(x)` is uniformly distributed on [ln(lo), ln(hi)).
+    /// Needs `0 < lo < hi`. Useful for parameters that vary over
+    /// orders of magnitude, such as learning rates.
+    fn rand_loguniform_f64(&mut self, lo: f64, hi: f64) -> f64 {
+        assert!(0.0 < lo && lo < hi, "rand_loguniform_f64: needs 0 < lo < hi");
+        let x = f64::exp(self.rand_range_f64(f64::ln(lo), f64::ln(hi)));
+        // The rounding of ln and exp may leave [lo, hi).
+        if x >= hi {prev_float(hi)} else {x.max(lo)}
     }
 
-    /// Shuffle an array randomly. The method is called Fisher–Yates shuffle and has linear time complexity.
-    fn shuffle<T>(&mut self, a: &mut [T]) {
-        if a.is_empty() {return;}
-        let mut i = a.len() - 1;
-        while i > 0 {
-            let j = self.rand_bounded_usize(i + 1);
-            a.swap(i, j);
-            i -= 1;
+    #[cfg(feature = "std")]
+    /// A sample from the Poisson distribution with mean `mu >= 0`,
+    /// which must be finite.
+    // For small mu, Knuth's multiplication method. Otherwise, the
+    // transformed rejection method PTRS of Hörmann (1993).
+    fn rand_poisson(&mut self, mu: f64) -> u64 {
+        assert!(mu >= 0.0 && mu.is_finite(),
+            "rand_poisson: mu must be finite and non-negative");
+        if mu < 10.0 {
+            let limit = f64::exp(-mu);
+            let mut k = 0;
+            let mut p = 1.0 - self.rand_f64();
+            while p > limit {
+                k += 1;
+                p *= 1.0 - self.rand_f64();
+            }
+            return k;
+        }
+        let ln_mu = f64::ln(mu);
+        let b = 0.931 + 2.53*f64::sqrt(mu);
+        let a = -0.059 + 0.02483*b;
+        let inv_alpha = 1.1239 + 1.1328/(b - 3.4);
+        let v_r = 0.9277 - 3.6224/(b - 2.0);
+        loop {
+            let u = self.rand_f64() - 0.5;
+            let v = self.rand_f64();
+            let us = 0.5 - u.abs();
+            let k = f64::floor((2.0*a/us + b)*u + mu + 0.43);
+            if us >= 0.07 && v <= v_r {return k as u64;}
+            if k < 0.0 || (us < 0.013 && v > us) {continue;}
+            if f64::ln(v*inv_alpha/(a/(us*us) + b)) <= k*ln_mu - mu - ln_factorial(k) {
+                return k as u64;
+            }
         }
     }
 
-    /// Fill a buffer with random bytes.
-    fn fill(&mut self, a: &mut[u8]) {
-        let mut x = self.rand_u32();
-        let mut count = 3;
-        for p in a {
-            *p = x as u8;
-            if count == 0 {
-                x = self.rand_u32();
-                count = 3;
-            } else {
-                x  >>= 8;
-                count -= 1;
+    #[cfg(feature = "std")]
+    /// A sample from the Skellam distribution, that is, the difference
+    /// of two Poisson distributed values with means `mu1 > 0`
+    /// and `mu2 > 0`.
+    fn rand_skellam(&mut self, mu1: f64, mu2: f64) -> i64 {
+        assert!(mu1 > 0.0 && mu2 > 0.0, "rand_skellam: rates must be positive");
+        self.rand_poisson(mu1) as i64 - self.rand_poisson(mu2) as i64
+    }
+
+    #[cfg(feature = "std")]
+    /// A sample from the standard normal distribution by the Ziggurat
+    /// method of Marsaglia and Tsang. This is faster than
+    /// `rand_normal_f64` for bulk generation, as most samples need
+    /// a single draw and no transcendental function.
+    /// Needs feature `std` to be enabled.
+    fn rand_normal_ziggurat(&mut self) -> f64 {
+        const R: f64 = ZIGGURAT_NORMAL_R;
+        let (zx, zy) = (&ZIGGURAT_NORMAL_X, &ZIGGURAT_NORMAL_Y);
+        loop {
+            // Layer from the high 8 bits, a value in [-1, 1) from the
+            // next 53 bits. The weak lowest bits are discarded.
+            let bits = self.rand_u64();
+            let i = (bits >> 56) as usize;
+            let u = 2.0*((bits >> 3 & 0x1f_ffff_ffff_ffff) as f64*(1.0/9007199254740992.0)) - 1.0;
+            let x = u*zx[i];
+            if x.abs() < zx[i + 1] {return x;}
+            if i == 0 {
+                // The tail beyond R, by Marsaglia's method.
+                loop {
+                    let a = -f64::ln(1.0 - self.rand_f64())/R;
+                    let b = -f64::ln(1.0 - self.rand_f64());
+                    if 2.0*b > a*a {
+                        return if u < 0.0 {-(R + a)} else {R + a};
+                    }
+                }
             }
+            let y = zy[i] + self.rand_f64()*(zy[i + 1] - zy[i]);
+            if y < f64::exp(-0.5*x*x) {return x;}
         }
     }
-}
 
-/** A helper function to turn random number generation into an iterator.
+    #[cfg(feature = "std")]
+    /// A sample from the standard exponential distribution, of rate 1,
+    /// by the Ziggurat method of Marsaglia and Tsang. Most samples need
+    /// a single draw and no logarithm.
+    /// Needs feature `std` to be enabled.
+    fn rand_exp_ziggurat(&mut self) -> f64 {
+        let (zx, zy) = (&ZIGGURAT_EXP_X, &ZIGGURAT_EXP_Y);
+        loop {
+            // Layer from the high 8 bits, a value in [0, 1) from the
+            // next 53 bits. The weak lowest bits are discarded.
+            let bits = self.rand_u64();
+            let i = (bits >> 56) as usize;
+            let x = (bits >> 3 & 0x1f_ffff_ffff_ffff) as f64*(1.0/9007199254740992.0)*zx[i];
+            if x < zx[i + 1] {return x;}
+            if i == 0 {
+                // The tail beyond R is R plus an exponential sample.
+                return ZIGGURAT_EXP_R - f64::ln(1.0 - self.rand_f64());
+            }
+            let y = zy[i] + self.rand_f64()*(zy[i + 1] - zy[i]);
+            if y < f64::exp(-x) {return x;}
+        }
+    }
 
-Example:
-```
-use tiny_rng::{Rng, Rand, rand_iter};
+    #[cfg(feature = "std")]
+    /// A sample from the exponential distribution with rate
+    /// `lambda > 0`, that is, of mean `1/lambda`.
+    /// Needs feature `std` to be enabled.
+    fn rand_exp_f64(&mut self, lambda: f64) -> f64 {
+        assert!(lambda > 0.0 && lambda.is_finite(), "rand_exp_f64: invalid rate");
+        self.rand_exp_ziggurat()/lambda
+    }
 
-let mut rng = Rng::from_seed(0);
-for x in rand_iter(&mut rng, Rand::rand_u32).take(10) {
-    println!("0x{:08x}", x);
-}
-```
-*/
-pub fn rand_iter<T: 'static, Generator: Rand>(
-    rng: &mut Generator,
-    rand: fn(&mut Generator) -> T
-) -> impl '_ + Iterator<Item = T>
-{
-    core::iter::from_fn(move || Some(rand(rng)))
-}
+    #[cfg(feature = "std")]
+    /// A point from the uniform distribution on the probability simplex
+    /// of dimension `n - 1`, that is, `n` non-negative coordinates with
+    /// sum 1. Equivalent to the Dirichlet distribution with all
+    /// parameters equal to 1. Needs `n > 0`.
+    // Normalized standard exponential samples.
+    fn rand_simplex(&mut self, n: usize) -> alloc::vec::Vec<f64> {
+        assert!(n > 0, "rand_simplex: n must be positive");
+        let mut x: alloc::vec::Vec<f64> = (0..n)
+            .map(|_| -f64::ln(1.0 - self.rand_f64())).collect();
+        let sum: f64 = x.iter().sum();
+        if sum == 0.0 {
+            x.fill(1.0/n as f64);
+        } else {
+            for xk in &mut x {*xk /= sum;}
+        }
+        x
+    }
 
-/*
-Xorshift128+ is a modern algorithm for the fast generation of random
-numbers of relatively high quality, which passes the most important
-statistical tests. The generator is said to have a period length of
-2^128-1 and to pass "BigCrush" from the test suite "TestU01". It should
-be noted that this is by no means a cryptographic generator. The
-following implementation is as described in Vignas article [1], with
-the assignment a=23, b=17, c=26 described there as favorable, and is
-also listed that way in the Firefox source code [2] and in
-Wikipedia [3][4]. The internal state may be arbitrary,
-but not (0, 0).
+    #[cfg(feature = "std")]
+    /// The edge list of a random graph from the Erdős–Rényi model
+    /// G(n, p): each of the `n(n-1)/2` undirected edges is included
+    /// independently with probability `p`. An edge is a pair `(i, j)`
+    /// with `i < j`, the list is sorted by `j`, then by `i`.
+    /// Needs feature `std` to be enabled.
+    // Batagelj and Brandes: the gap to the next included edge is
+    // geometrically distributed, so the work is proportional to the
+    // number of edges rather than the number of pairs.
+    fn erdos_renyi(&mut self, n: usize, p: f64) -> alloc::vec::Vec<(usize, usize)> {
+        assert!((0.0..=1.0).contains(&p), "erdos_renyi: p must be in [0, 1]");
+        let mut edges = alloc::vec::Vec::new();
+        if p == 0.0 {return edges;}
+        if p == 1.0 {
+            for j in 1..n {
+                for i in 0..j {edges.push((i, j));}
+            }
+            return edges;
+        }
+        // ln(1 - p) by ln_1p, as 1.0 - p rounds to 1.0 for tiny p.
+        let lp = (-p).ln_1p();
+        let (mut i, mut j): (usize, usize) = (0, 1);
+        while j < n {
+            let skip = ((-self.rand_f64()).ln_1p()/lp).floor() as usize;
+            i = i.saturating_add(skip);
+            while i >= j && j < n {
+                i -= j;
+                j += 1;
+            }
+            if j < n {
+                edges.push((i, j));
+                i += 1;
+            }
+        }
+        edges
+    }
 
-Since the low-order 32 bits of a random value of Xorshift128+ are not
-supposed to pass some statistical tests, it is better to extract
-the high-order 32 bits for rand_u32.
+    #[cfg(feature = "std")]
+    /// A sample from the multivariate normal distribution with the
+    /// given mean and the covariance matrix `L L^T`, written to `out`.
+    /// The Cholesky factor `L` is a lower triangular `n*n` matrix,
+    /// stored row-major as a slice of length `n*n`, where entry
+    /// `(i, j)` is at index `i*n + j`. The entries of the upper
+    /// triangle are ignored.
+    // mean + L z, where z is standard normal.
+    fn rand_multivariate_normal(&mut self, mean: &[f64], cholesky_lower: &[f64],
+        n: usize, out: &mut [f64])
+    {
+        assert!(mean.len() == n && out.len() == n && cholesky_lower.len() == n*n,
+            "rand_multivariate_normal: dimension mismatch");
+        for z in out.iter_mut() {
+            *z = self.rand_normal_f64(0.0, 1.0);
+        }
+        // Row i only depends on z[0..=i], thus proceed backwards in place.
+        for i in (0..n).rev() {
+            let row = &cholesky_lower[i*n..=i*n + i];
+            let y: f64 = row.iter().zip(&out[..=i]).map(|(l, z)| l*z).sum();
+            out[i] = mean[i] + y;
+        }
+    }
 
-[1] Sebastiano Vigna: "Further scramblings of Marsaglia's
-xorshift generators".
-Journal of Computational and Applied Mathematics (Mai 2017).
-arXiv:1404.0390. doi:10.1016/j.cam.2016.11.006.
-https://arxiv.org/abs/1404.0390
+    /// A point from the uniform distribution on the axis-aligned box
+    /// with the corners `min` and `max`, written to `out`. Each
+    /// coordinate is drawn by `rand_range_inclusive_f64`, so the point
+    /// never lies outside of the closed box.
+    fn rand_in_box(&mut self, min: &[f64], max: &[f64], out: &mut [f64]) {
+        assert!(min.len() == out.len() && max.len() == out.len(),
+            "rand_in_box: dimension mismatch");
+        for ((x, &a), &b) in out.iter_mut().zip(min).zip(max) {
+            *x = self.rand_range_inclusive_f64(a, b);
+        }
+    }
 
-[2] mfbt/XorShift128PlusRNG.h, retrieved 8 Feb. 2020.
-https://github.com/mozilla/gecko-dev/blob/master/mfbt/XorShift128PlusRNG.h
--- Gecko is the current engine of Firefox.
+    #[cfg(feature = "std")]
+    /// A point from the uniform distribution on the triangle
+    /// with the vertices `a`, `b`, `c`.
+    /// Needs feature `std` to be enabled.
+    // The barycentric coordinates (1 - s, s(1 - v), s v) with
+    // s = sqrt(u) are uniform on the triangle.
+    fn rand_in_triangle(&mut self, a: (f64, f64), b: (f64, f64), c: (f64, f64))
+    -> (f64, f64)
+    {
+        let s = f64::sqrt(self.rand_f64());
+        let v = self.rand_f64();
+        let (wa, wb, wc) = (1.0 - s, s*(1.0 - v), s*v);
+        (wa*a.0 + wb*b.0 + wc*c.0, wa*a.1 + wb*b.1 + wc*c.1)
+    }
 
-[3] Xorshift. English Wikipedia, retrieved 8 Feb. 2020.
-https://en.wikipedia.org/wiki/Xorshift
+    #[cfg(feature = "std")]
+    /// A point from the uniform distribution on the annulus
+    /// `r_inner <= |p| <= r_outer` in the plane. Needs
+    /// `0 <= r_inner <= r_outer` with finite radii, `r_inner == r_outer`
+    /// gives a point on the circle.
+    /// Needs feature `std` to be enabled.
+    // The squared radius is uniform, by the area element r dr.
+    fn rand_in_annulus(&mut self, r_inner: f64, r_outer: f64) -> (f64, f64) {
+        assert!(0.0 <= r_inner && r_inner <= r_outer && r_outer.is_finite(),
+            "rand_in_annulus: invalid radii {} and {}", r_inner, r_outer);
+        let (a, b) = (r_inner*r_inner, r_outer*r_outer);
+        let r = f64::sqrt(a + self.rand_f64()*(b - a)).clamp(r_inner, r_outer);
+        let phi = core::f64::consts::TAU*self.rand_f64();
+        let (s, c) = phi.sin_cos();
+        (r*c, r*s)
+    }
 
-[4] Xorshift. German Wikipedia, retrievend 8 Feb. 2020.
-https://de.wikipedia.org/wiki/Xorshift.
-*/
+    #[cfg(feature = "std")]
+    /// A random rotation of the plane from the uniform distribution,
+    /// as a 2x2 matrix in row-major order.
+    fn rand_rotation_2d(&mut self) -> [f64; 4] {
+        let phi = core::f64::consts::TAU*self.rand_f64();
+        let (s, c) = phi.sin_cos();
+        [c, -s, s, c]
+    }
 
-/// Recommended engine. Currently Xorshift128+.
-pub struct Rng {
-    state: (u64, u64)
-}
+    #[cfg(feature = "std")]
+    /// A random unit quaternion `[w, x, y, z]` from the uniform
+    /// distribution on the unit sphere in four dimensions, which
+    /// represents a uniformly random orientation.
+    // Shoemake's method.
+    fn rand_quaternion(&mut self) -> [f64; 4] {
+        let u1 = self.rand_f64();
+        let (s2, c2) = f64::sin_cos(core::f64::consts::TAU*self.rand_f64());
+        let (s3, c3) = f64::sin_cos(core::f64::consts::TAU*self.rand_f64());
+        let r1 = f64::sqrt(1.0 - u1);
+        let r2 = f64::sqrt(u1);
+        [r2*c3, r1*s2, r1*c2, r2*s3]
+    }
 
-impl Rand for Rng {
-    fn from_seed(seed: u64) -> Self {
-        Self {state: (
-            seed ^ 0xf4dbdf2183dcefb7, // [crc32(b"0"), crc32(b"1")]
-            seed ^ 0x1ad5be0d6dd28e9b  // [crc32(b"2"), crc32(b"3")]
-        )}
+    #[cfg(feature = "std")]
+    /// A random rotation of space from the uniform distribution
+    /// (Haar measure), as a 3x3 matrix in row-major order.
+    fn rand_rotation_3d(&mut self) -> [f64; 9] {
+        let [w, x, y, z] = self.rand_quaternion();
+        [
+            1.0 - 2.0*(y*y + z*z), 2.0*(x*y - w*z), 2.0*(x*z + w*y),
+            2.0*(x*y + w*z), 1.0 - 2.0*(x*x + z*z), 2.0*(y*z - w*x),
+            2.0*(x*z - w*y), 2.0*(y*z + w*x), 1.0 - 2.0*(x*x + y*y)
+        ]
     }
 
-    fn rand_u64(&mut self) -> u64 {
-        let (mut x, y) = self.state;
-        self.state.0 = y;
-        x ^= x << 23;
-        self.state.1 = x ^ y ^ (x >> 17) ^ (y >> 26);
-        self.state.1.wrapping_add(y)
+    #[cfg(feature = "std")]
+    /// A path of geometric Brownian motion with drift `mu` and
+    /// volatility `sigma`, starting at `s0`, sampled at `steps` time
+    /// steps of length `dt`. The result has `steps + 1` values,
+    /// starting with `s0`. The discretization is exact: each step
+    /// multiplies by `exp((mu - sigma^2/2)*dt + sigma*sqrt(dt)*z)`,
+    /// where `z` is standard normal.
+    fn gbm_path(&mut self, s0: f64, mu: f64, sigma: f64, dt: f64, steps: usize)
+    -> alloc::vec::Vec<f64>
+    {
+        let drift = (mu - 0.5*sigma*sigma)*dt;
+        let vol = sigma*f64::sqrt(dt);
+        let mut path = alloc::vec::Vec::with_capacity(steps + 1);
+        let mut s = s0;
+        path.push(s);
+        for _ in 0..steps {
+            s *= f64::exp(drift + vol*self.rand_normal_f64(0.0, 1.0));
+            path.push(s);
+        }
+        path
     }
 
-    #[inline]
-    fn rand_u32(&mut self) -> u32 {
-        (self.rand_u64() >> 32) as u32
+    #[cfg(feature = "alloc")]
+    /// The positions of a symmetric ±1 random walk on the integers,
+    /// starting at 0. The result has `steps + 1` values, starting
+    /// with 0. Needs feature `alloc` to be enabled.
+    fn random_walk_1d(&mut self, steps: usize) -> alloc::vec::Vec<i64> {
+        let mut path = alloc::vec::Vec::with_capacity(steps + 1);
+        let mut x: i64 = 0;
+        path.push(x);
+        for _ in 0..steps {
+            x += self.rand_sign() as i64;
+            path.push(x);
+        }
+        path
     }
-}
 
-impl Rng {
-    /** A helper function to turn random number generation into an iterator.
+    #[cfg(feature = "std")]
+    /// A sample from the gamma distribution with shape `k > 0`
+    /// and scale `theta > 0`.
+    // Marsaglia–Tsang method. For k < 1, the relation
+    // Gamma(k) = Gamma(k + 1)*U^(1/k) is applied.
+    fn rand_gamma_f64(&mut self, k: f64, theta: f64) -> f64 {
+        assert!(k > 0.0 && theta > 0.0, "rand_gamma_f64: invalid parameters");
+        if k < 1.0 {
+            let u = 1.0 - self.rand_f64();
+            return self.rand_gamma_f64(k + 1.0, theta)*u.powf(1.0/k);
+        }
+        let d = k - 1.0/3.0;
+        let c = 1.0/f64::sqrt(9.0*d);
+        loop {
+            let x = self.rand_normal_f64(0.0, 1.0);
+            let v = 1.0 + c*x;
+            if v <= 0.0 {continue;}
+            let v = v*v*v;
+            let u = 1.0 - self.rand_f64();
+            if f64::ln(u) < 0.5*x*x + d - d*v + d*f64::ln(v) {
+                return theta*d*v;
+            }
+        }
+    }
 
-    Example:
-    ```
-    use tiny_rng::{Rng, Rand};
+    #[cfg(feature = "std")]
+    /// A sample from the chi-squared distribution with `k > 0`
+    /// degrees of freedom.
+    fn rand_chi_squared_f64(&mut self, k: f64) -> f64 {
+        assert!(k > 0.0, "rand_chi_squared_f64: k must be positive");
+        self.rand_gamma_f64(0.5*k, 2.0)
+    }
 
-    let mut rng = Rng::from_seed(0);
-    for x in rng.iter(Rand::rand_u32).take(10) {
-        println!("0x{:08x}", x);
+    #[cfg(feature = "std")]
+    /// A sample from the F-distribution with `d1 > 0` and `d2 > 0`
+    /// degrees of freedom.
+    fn rand_f_distribution_f64(&mut self, d1: f64, d2: f64) -> f64 {
+        assert!(d1 > 0.0 && d2 > 0.0,
+            "rand_f_distribution_f64: degrees of freedom must be positive");
+        let x1 = self.rand_chi_squared_f64(d1);
+        let x2 = self.rand_chi_squared_f64(d2);
+        (x1/d1)/(x2/d2)
+    }
+    
+    /// A sample from the uniform distribution on the non-empty slice.
+    fn choice<'a, T>(&mut self, a: &'a [T]) -> &'a T {
+        assert!(!a.is_empty(), "choice: slice must be non-empty");
+        &a[self.rand_bounded_usize(a.len())]
+    }
+
+    /// Like `choice`, but for an array whose length is known at compile
+    /// time. An empty array is rejected at compile time:
+    /// ```compile_fail
+    /// use tiny_rng::{Rng, Rand};
+    /// let a: [u32; 0] = [];
+    /// Rng::from_seed(0).choice_array(&a);
+    /// ```
+    fn choice_array<'a, T, const N: usize>(&mut self, a: &'a [T; N]) -> &'a T {
+        let () = NonEmpty::<N>::OK;
+        &a[self.rand_bounded_usize(N)]
+    }
+
+    /// A sample from the uniform distribution on the range,
+    /// or `None` if the range is empty.
+    fn choice_range(&mut self, range: core::ops::Range<usize>) -> Option<usize> {
+        if range.start >= range.end {return None;}
+        Some(range.start + self.rand_bounded_usize(range.end - range.start))
+    }
+
+    /// A random index, where each index `i` is chosen with probability
+    /// proportional to `weights[i]`. The weights must be finite and
+    /// non-negative, and not all zero.
+    fn rand_index_weighted(&mut self, weights: &[f64]) -> usize {
+        assert!(weights.iter().all(|&w| w >= 0.0 && w.is_finite()),
+            "rand_index_weighted: invalid weight");
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0, "rand_index_weighted: total weight is zero");
+        weighted_index(self.rand_f64()*total, weights.iter().copied())
+    }
+
+    #[cfg(feature = "std")]
+    /// A random index, where each index `i` is chosen with probability
+    /// `exp(logits[i])/sum(exp(logits))`, the softmax of the logits.
+    /// Logits of negative infinity are never chosen. The slice must
+    /// not be empty, must not contain NaN and must contain at least one
+    /// logit greater than negative infinity.
+    /// Needs feature `std` to be enabled.
+    // Gumbel-max trick: the argmax of the logits perturbed by
+    // independent standard Gumbel noise. No exponentiation is
+    // needed, so large logits cannot overflow.
+    fn sample_logits(&mut self, logits: &[f64]) -> usize {
+        self.sample_logits_temperature(logits, 1.0)
+    }
+
+    #[cfg(feature = "std")]
+    /// Like `sample_logits`, but the logits are divided by the
+    /// temperature first. Low temperatures concentrate on the largest
+    /// logit, high temperatures approach the uniform distribution.
+    /// At temperature zero, the index of the first largest logit is
+    /// returned, without consuming random numbers. The temperature
+    /// must be finite and non-negative.
+    /// Needs feature `std` to be enabled.
+    fn sample_logits_temperature(&mut self, logits: &[f64], temperature: f64) -> usize {
+        assert!(!logits.is_empty(), "sample_logits_temperature: no logits");
+        assert!(logits.iter().all(|x| !x.is_nan()), "sample_logits_temperature: NaN logit");
+        assert!(logits.iter().any(|&x| x > f64::NEG_INFINITY),
+            "sample_logits_temperature: all logits are negative infinity");
+        assert!(temperature >= 0.0 && temperature.is_finite(),
+            "sample_logits_temperature: invalid temperature");
+        let mut best = 0;
+        let mut best_value = f64::NEG_INFINITY;
+        if temperature == 0.0 {
+            for (i, &x) in logits.iter().enumerate() {
+                if x > best_value {
+                    best = i;
+                    best_value = x;
+                }
+            }
+            return best;
+        }
+        for (i, &x) in logits.iter().enumerate() {
+            // Uniform on the open interval (0, 1).
+            let u = (self.rand_u32() as f64 + 0.5)*2.3283064365386963E-10;
+            let value = x/temperature - f64::ln(-f64::ln(u));
+            if value > best_value {
+                best = i;
+                best_value = value;
+            }
+        }
+        best
+    }
+
+    #[cfg(feature = "std")]
+    /// Like `sample_logits`, but restricted to the `k` largest logits,
+    /// renormalized. Ties are broken by the smaller index. Needs
+    /// `k > 0` and a logit greater than negative infinity.
+    /// Needs feature `std` to be enabled.
+    fn sample_top_k(&mut self, logits: &[f64], k: usize) -> usize {
+        assert!(k > 0, "sample_top_k: k must be positive");
+        assert!(!logits.is_empty(), "sample_top_k: no logits");
+        assert!(logits.iter().all(|x| !x.is_nan()), "sample_top_k: NaN logit");
+        assert!(logits.iter().any(|&x| x > f64::NEG_INFINITY),
+            "sample_top_k: all logits are negative infinity");
+        let mut filtered = alloc::vec![f64::NEG_INFINITY; logits.len()];
+        for i in descending_order(logits).into_iter().take(k) {
+            filtered[i] = logits[i];
+        }
+        self.sample_logits(&filtered)
+    }
+
+    #[cfg(feature = "std")]
+    /// Nucleus sampling: like `sample_logits`, but restricted to the
+    /// smallest set of largest logits whose softmax probabilities sum
+    /// to at least `p`, renormalized. Needs `0 < p <= 1` and a finite
+    /// largest logit. Needs feature `std` to be enabled.
+    fn sample_top_p(&mut self, logits: &[f64], p: f64) -> usize {
+        assert!(p > 0.0 && p <= 1.0, "sample_top_p: p must be in (0, 1]");
+        assert!(logits.iter().all(|x| !x.is_nan()), "sample_top_p: NaN logit");
+        let order = descending_order(logits);
+        assert!(!order.is_empty(), "sample_top_p: no logits");
+        let max = logits[order[0]];
+        assert!(max.is_finite(), "sample_top_p: largest logit is not finite");
+        let total: f64 = logits.iter().map(|&x| f64::exp(x - max)).sum();
+        let mut filtered = alloc::vec![f64::NEG_INFINITY; logits.len()];
+        let mut acc = 0.0;
+        for i in order {
+            filtered[i] = logits[i];
+            acc += f64::exp(logits[i] - max);
+            if acc >= p*total {break;}
+        }
+        self.sample_logits(&filtered)
+    }
+
+    /// A random index, where each index `i` is chosen with probability
+    /// `pmf[i]`. A sum off from 1 by rounding is tolerated, as the
+    /// probabilities are renormalized by their sum. The result is always
+    /// a valid index of positive probability, see `rand_index_weighted`.
+    fn sample_pmf(&mut self, pmf: &[f64]) -> usize {
+        self.rand_index_weighted(pmf)
+    }
+
+    /// A random index from a cumulative distribution: index `i` is
+    /// chosen with probability `cdf[i] - cdf[i - 1]`, where `cdf[-1]`
+    /// is taken as zero. The slice must be non-empty and non-decreasing,
+    /// which is checked in debug builds, and should end at 1.0.
+    // Binary search in O(log n).
+    fn sample_cdf(&mut self, cdf: &[f64]) -> usize {
+        assert!(!cdf.is_empty(), "sample_cdf: empty slice");
+        debug_assert!(cdf.windows(2).all(|w| w[0] <= w[1]),
+            "sample_cdf: the CDF must be non-decreasing");
+        let u = self.rand_f64();
+        // A last value slightly below 1.0 leaves a gap, assigned to
+        // the last index.
+        cdf.partition_point(|&c| c <= u).min(cdf.len() - 1)
+    }
+
+    /// A sample from the slice, where each element is chosen with
+    /// probability proportional to its weight, see `rand_index_weighted`.
+    fn choice_weighted<'a, T>(&mut self, items: &'a [T], weights: &[f64]) -> &'a T {
+        assert_eq!(items.len(), weights.len(), "choice_weighted: lengths differ");
+        &items[self.rand_index_weighted(weights)]
+    }
+
+    /// Like `choice_weighted`, but returns a mutable reference.
+    fn choice_weighted_mut<'a, T>(&mut self, items: &'a mut [T], weights: &[f64]) -> &'a mut T {
+        assert_eq!(items.len(), weights.len(), "choice_weighted_mut: lengths differ");
+        &mut items[self.rand_index_weighted(weights)]
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A random index from the uniform distribution on `0..n` without
+    /// the indices in `excluded`, or `None` if all are excluded. For
+    /// small exclusion sets, rejection sampling is applied, otherwise
+    /// the list of allowed indices is built.
+    /// Needs feature `alloc` to be enabled.
+    fn choice_excluding(&mut self, n: usize, excluded: &[usize]) -> Option<usize> {
+        if n > 0 && excluded.len() <= n/2 {
+            // At least half of the indices are allowed.
+            loop {
+                let i = self.rand_bounded_usize(n);
+                if !excluded.contains(&i) {return Some(i);}
+            }
+        }
+        let mut allowed = alloc::vec![true; n];
+        for &i in excluded {
+            if i < n {allowed[i] = false;}
+        }
+        let allowed: alloc::vec::Vec<usize> = (0..n).filter(|&i| allowed[i]).collect();
+        if allowed.is_empty() {return None;}
+        Some(*self.choice(&allowed))
+    }
+
+    #[cfg(feature = "std")]
+    /// A key of the map, chosen with probability proportional to its
+    /// weight, or `None` if the map is empty. The weights must be
+    /// finite and non-negative, and not all zero. The result depends
+    /// on the iteration order of the map, thus with the default
+    /// `RandomState` it is not reproducible across runs, even from
+    /// the same seed. A deterministic `BuildHasher` makes it so.
+    /// Needs feature `std` to be enabled.
+    fn choice_weighted_map<'a, K, S>(&mut self, map: &'a std::collections::HashMap<K, f64, S>)
+    -> Option<&'a K>
+    {
+        if map.is_empty() {return None;}
+        assert!(map.values().all(|&w| w >= 0.0 && w.is_finite()),
+            "choice_weighted_map: invalid weight");
+        let total: f64 = map.values().sum();
+        assert!(total > 0.0, "choice_weighted_map: total weight is zero");
+        let i = weighted_index(self.rand_f64()*total, map.values().copied());
+        // An unmodified map iterates in the same order again.
+        map.keys().nth(i)
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A random index in `0..n`, where the indices in `recent` are
+    /// chosen `penalty` times as often as the others. Needs `n > 0`,
+    /// `penalty` in `[0, 1]` and the recent indices below `n`. If all
+    /// indices are recent and the penalty is zero, the choice falls
+    /// back to the uniform distribution.
+    /// Needs feature `alloc` to be enabled.
+    fn choice_avoiding_recent(&mut self, n: usize, recent: &[usize], penalty: f64) -> usize {
+        assert!(n > 0, "choice_avoiding_recent: n must be positive");
+        assert!((0.0..=1.0).contains(&penalty),
+            "choice_avoiding_recent: penalty must be in [0, 1]");
+        let mut weights = alloc::vec![1.0; n];
+        for &i in recent {
+            assert!(i < n, "choice_avoiding_recent: index out of range");
+            weights[i] = penalty;
+        }
+        if weights.iter().all(|&w| w == 0.0) {
+            return self.rand_bounded_usize(n);
+        }
+        self.rand_index_weighted(&weights)
+    }
+
+    /// A sample from the slice, where each element is chosen with
+    /// probability proportional to its integer weight. The arithmetic
+    /// is exact. The weights must not all be zero.
+    fn choice_weighted_u32<'a, T>(&mut self, items: &'a [T], weights: &[u32]) -> &'a T {
+        assert_eq!(items.len(), weights.len(), "choice_weighted_u32: lengths differ");
+        let total: u64 = weights.iter().map(|&w| w as u64).sum();
+        assert!(total > 0, "choice_weighted_u32: total weight is zero");
+        let mut x = self.rand_bounded_u64(total);
+        for (item, &w) in items.iter().zip(weights) {
+            if x < w as u64 {return item;}
+            x -= w as u64;
+        }
+        unreachable!()
+    }
+
+    /// Shuffle an array randomly. The method is called Fisher–Yates shuffle and has linear time complexity.
+    fn shuffle<T>(&mut self, a: &mut [T]) {
+        if a.is_empty() {return;}
+        let mut i = a.len() - 1;
+        while i > 0 {
+            let j = self.rand_bounded_usize(i + 1);
+            a.swap(i, j);
+            i -= 1;
+        }
+    }
+
+    /// Shuffle an array randomly like `shuffle`, but with the same
+    /// permutation on all targets. The indices are always drawn by
+    /// `rand_bounded_u64`, whereas `shuffle` depends on the pointer
+    /// width. On 64-bit targets, both methods agree.
+    fn shuffle_portable<T>(&mut self, a: &mut [T]) {
+        let mut i = a.len();
+        while i > 1 {
+            i -= 1;
+            let j = self.rand_bounded_u64(i as u64 + 1) as usize;
+            a.swap(i, j);
+        }
+    }
+
+    /// Shuffle two arrays of the same length by the same random
+    /// permutation, which keeps paired elements together.
+    fn shuffle_pair<A, B>(&mut self, a: &mut [A], b: &mut [B]) {
+        assert_eq!(a.len(), b.len(), "shuffle_pair: lengths differ");
+        if a.is_empty() {return;}
+        let mut i = a.len() - 1;
+        while i > 0 {
+            let j = self.rand_bounded_usize(i + 1);
+            a.swap(i, j);
+            b.swap(i, j);
+            i -= 1;
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A random subset of the slice, where each element is included
+    /// independently with probability `frac`, keeping the order. The
+    /// size of the result varies, its expectation is `frac*a.len()`.
+    /// Needs `0 <= frac <= 1` and feature `alloc` to be enabled.
+    fn sample_fraction<'a, T>(&mut self, a: &'a [T], frac: f64) -> alloc::vec::Vec<&'a T> {
+        assert!((0.0..=1.0).contains(&frac), "sample_fraction: needs 0 <= frac <= 1");
+        a.iter().filter(|_| self.rand_f64() < frac).collect()
+    }
+
+    #[cfg(feature = "std")]
+    /// A weighted random sample of `k` items from a stream of
+    /// `(item, weight)` pairs of unknown length, without replacement.
+    /// Each draw takes an item with probability proportional to its
+    /// weight among the remaining ones. Items of weight zero are never
+    /// taken, so fewer than `k` items may be returned. The weights
+    /// must be finite and non-negative. The order of the result is
+    /// unspecified. Needs feature `std` to be enabled.
+    // Efraimidis–Spirakis A-Res: keep the k largest keys u^(1/w),
+    // compared as ln(u)/w to avoid underflow.
+    fn weighted_reservoir<T>(&mut self, iter: impl Iterator<Item = (T, f64)>, k: usize)
+    -> alloc::vec::Vec<T>
+    {
+        struct Entry<T>(f64, T);
+        impl<T> PartialEq for Entry<T> {
+            fn eq(&self, other: &Self) -> bool {self.0 == other.0}
+        }
+        impl<T> Eq for Entry<T> {}
+        impl<T> PartialOrd for Entry<T> {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        // Reversed, so that the heap's top is the smallest key.
+        impl<T> Ord for Entry<T> {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                other.0.total_cmp(&self.0)
+            }
+        }
+        if k == 0 {return alloc::vec::Vec::new();}
+        let mut heap = std::collections::BinaryHeap::with_capacity(k);
+        for (item, w) in iter {
+            assert!(w >= 0.0 && w.is_finite(), "weighted_reservoir: invalid weight");
+            if w == 0.0 {continue;}
+            // Uniform on the open interval (0, 1).
+            let u = (self.rand_u32() as f64 + 0.5)*2.3283064365386963E-10;
+            let key = f64::ln(u)/w;
+            if heap.len() < k {
+                heap.push(Entry(key, item));
+            } else if key > heap.peek().unwrap().0 {
+                heap.pop();
+                heap.push(Entry(key, item));
+            }
+        }
+        heap.into_iter().map(|e| e.1).collect()
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A perfect maze of `width*height` cells, in which any two cells
+    /// are connected by exactly one path. The result is the wall grid
+    /// of `(2*width + 1)*(2*height + 1)` squares in row-major order,
+    /// where `true` is a wall. The cell `(x, y)` is the square
+    /// `(2*x + 1, 2*y + 1)`, the squares between neighboring cells are
+    /// the passages, the others are always walls.
+    /// Needs feature `alloc` to be enabled.
+    // Randomized depth-first search.
+    fn random_maze(&mut self, width: usize, height: usize) -> alloc::vec::Vec<bool> {
+        let gw = 2*width + 1;
+        let mut grid = alloc::vec![true; gw*(2*height + 1)];
+        if width == 0 || height == 0 {return grid;}
+        let mut visited = alloc::vec![false; width*height];
+        let mut stack = alloc::vec![(0, 0)];
+        visited[0] = true;
+        grid[gw + 1] = false;
+        while let Some(&(x, y)) = stack.last() {
+            let mut next = [(0, 0); 4];
+            let mut count = 0;
+            if x > 0 {next[count] = (x - 1, y); count += 1;}
+            if y > 0 {next[count] = (x, y - 1); count += 1;}
+            if x + 1 < width {next[count] = (x + 1, y); count += 1;}
+            if y + 1 < height {next[count] = (x, y + 1); count += 1;}
+            let mut unvisited = [(0, 0); 4];
+            let mut n = 0;
+            for &(nx, ny) in &next[..count] {
+                if !visited[ny*width + nx] {unvisited[n] = (nx, ny); n += 1;}
+            }
+            if n == 0 {
+                stack.pop();
+                continue;
+            }
+            let (nx, ny) = *self.choice(&unvisited[..n]);
+            visited[ny*width + nx] = true;
+            // Open the passage and the new cell.
+            grid[(y + ny + 1)*gw + x + nx + 1] = false;
+            grid[(2*ny + 1)*gw + 2*nx + 1] = false;
+            stack.push((nx, ny));
+        }
+        grid
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Shuffle an array randomly and return the applied permutation
+    /// `perm`, such that the new `a[i]` is the old `a[perm[i]]`.
+    /// Needs feature `alloc` to be enabled.
+    fn shuffle_tracked<T>(&mut self, a: &mut [T]) -> alloc::vec::Vec<usize> {
+        let mut perm: alloc::vec::Vec<usize> = (0..a.len()).collect();
+        self.shuffle_pair(a, &mut perm);
+        perm
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Shuffle an array randomly like `shuffle` and record the swaps,
+    /// so that the shuffle can be reversed by `unshuffle`.
+    /// Needs feature `alloc` to be enabled.
+    fn shuffle_recording<T>(&mut self, a: &mut [T]) -> ShuffleLog {
+        let mut swaps = alloc::vec::Vec::with_capacity(a.len());
+        let mut i = a.len();
+        while i > 1 {
+            i -= 1;
+            let j = self.rand_bounded_usize(i + 1);
+            a.swap(i, j);
+            swaps.push(j);
+        }
+        ShuffleLog {len: a.len(), swaps}
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Shuffle a deque randomly in place, like `shuffle`.
+    /// Needs feature `alloc` to be enabled.
+    fn shuffle_deque<T>(&mut self, d: &mut alloc::collections::VecDeque<T>) {
+        let mut i = d.len();
+        while i > 1 {
+            i -= 1;
+            let j = self.rand_bounded_usize(i + 1);
+            d.swap(i, j);
+        }
+    }
+
+    /// Shuffle an array by a permutation from the uniform distribution
+    /// on the even permutations if `even` is true, otherwise on the odd
+    /// ones. The parity of a regular shuffle is corrected by a final
+    /// transposition, which is a bijection between both classes.
+    /// An array of fewer than two elements has no odd permutation,
+    /// which leads to a panic.
+    fn shuffle_with_parity<T>(&mut self, a: &mut [T], even: bool) {
+        assert!(even || a.len() >= 2, "shuffle_with_parity: no odd permutation");
+        let mut odd = false;
+        let mut i = a.len();
+        while i > 1 {
+            i -= 1;
+            let j = self.rand_bounded_usize(i + 1);
+            a.swap(i, j);
+            odd ^= i != j;
+        }
+        if odd == even {a.swap(0, 1);}
+    }
+
+    /// Shuffle an array randomly and split it into the first `left_len`
+    /// elements and the rest, for example into a training and
+    /// a validation set. Needs `left_len <= a.len()`.
+    fn split_shuffle<'a, T>(&mut self, a: &'a mut [T], left_len: usize)
+    -> (&'a mut [T], &'a mut [T])
+    {
+        assert!(left_len <= a.len(), "split_shuffle: left_len out of bounds");
+        self.shuffle(a);
+        a.split_at_mut(left_len)
+    }
+
+    #[cfg(feature = "alloc")]
+    /// The indices `0..n` in random order, drawn lazily without
+    /// replacement. Each step performs one step of the Fisher–Yates
+    /// shuffle, thus stopping early saves work.
+    /// Needs feature `alloc` to be enabled.
+    fn draw_indices(&mut self, n: usize) -> DrawIndices<'_, Self> {
+        DrawIndices {rng: self, perm: (0..n).collect(), pos: 0}
+    }
+
+    /// Rotate an array to the left by a random amount from the uniform
+    /// distribution on `0..a.len()`. In contrast to a shuffle, the cyclic
+    /// order of the elements is kept.
+    fn rotate_random<T>(&mut self, a: &mut [T]) {
+        if a.is_empty() {return;}
+        let k = self.rand_bounded_usize(a.len());
+        a.rotate_left(k);
+    }
+
+    /// Fill a buffer with random bytes.
+    fn fill(&mut self, a: &mut[u8]) {
+        for_each_random_byte(self, a, |p, x| *p = x);
+    }
+
+    /// XOR the bytes `fill` would write into the buffer. Applying it
+    /// twice from the same generator state restores the buffer.
+    fn xor_fill(&mut self, a: &mut[u8]) {
+        for_each_random_byte(self, a, |p, x| *p ^= x);
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A vector of `n` random bytes.
+    /// Needs feature `alloc` to be enabled.
+    fn rand_bytes(&mut self, n: usize) -> alloc::vec::Vec<u8> {
+        let mut a = alloc::vec![0; n];
+        self.fill(&mut a);
+        a
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A vector of `n` fair random booleans.
+    /// Needs feature `alloc` to be enabled.
+    fn rand_bits(&mut self, n: usize) -> alloc::vec::Vec<bool> {
+        let words = self.rand_bitset(n);
+        (0..n).map(|i| words[i/64] >> (i%64) & 1 == 1).collect()
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A random bitset of `n` bits, packed into `ceil(n/64)` words,
+    /// least significant bit first. The unused bits of the last word
+    /// are zero. Needs feature `alloc` to be enabled.
+    fn rand_bitset(&mut self, n: usize) -> alloc::vec::Vec<u64> {
+        let tail = n%64;
+        let mut words: alloc::vec::Vec<u64> = (0..n/64 + (tail > 0) as usize)
+            .map(|_| self.rand_u64()).collect();
+        if tail > 0 {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << tail) - 1;
+            }
+        }
+        words
+    }
+
+    /// A random character from the uniform distribution
+    /// on all Unicode scalar values.
+    fn rand_char(&mut self) -> char {
+        // Skip the surrogates 0xd800..0xe000.
+        let x = self.rand_bounded_u32(0x110000 - 0x800);
+        let x = if x >= 0xd800 {x + 0x800} else {x};
+        char::from_u32(x).unwrap()
+    }
+
+    /// A random digit of the given radix `2..=36`, one of
+    /// the characters `0-9a-z`, from the uniform distribution.
+    fn rand_digit_radix(&mut self, radix: u32) -> char {
+        assert!((2..=36).contains(&radix), "rand_digit_radix: radix must be in 2..=36");
+        char::from_digit(self.rand_bounded_u32(radix), radix).unwrap()
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A random valid UTF-8 string of at most `max_bytes` bytes, for
+    /// fuzzing. Characters from `rand_char` are appended until the next
+    /// one would exceed the budget. Needs feature `alloc` to be enabled.
+    fn rand_utf8_string(&mut self, max_bytes: usize) -> alloc::string::String {
+        let mut s = alloc::string::String::with_capacity(max_bytes);
+        loop {
+            let c = self.rand_char();
+            if s.len() + c.len_utf8() > max_bytes {return s;}
+            s.push(c);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// A vector of `n` pairwise distinct samples from the uniform
+    /// distribution on `0..=u64::MAX`. Repeated values are rejected.
+    /// In theory, this may loop for a long time, but for any reasonable
+    /// `n` a collision is astronomically rare.
+    /// Needs feature `std` to be enabled.
+    fn distinct_u64(&mut self, n: usize) -> alloc::vec::Vec<u64> {
+        let mut seen = std::collections::HashSet::with_capacity(n);
+        let mut a = alloc::vec::Vec::with_capacity(n);
+        while a.len() < n {
+            let x = self.rand_u64();
+            if seen.insert(x) {a.push(x);}
+        }
+        a
+    }
+
+    #[cfg(feature = "alloc")]
+    /// A weak composition of `total` into `parts` from the uniform
+    /// distribution, that is, `parts` non-negative integers with sum
+    /// `total`, where the order matters. For `parts == 0`, only
+    /// `total == 0` is possible.
+    /// Needs feature `alloc` to be enabled.
+    // Stars and bars: `parts - 1` distinct bar positions among
+    // `total + parts - 1` slots, drawn by Floyd's algorithm.
+    fn rand_composition(&mut self, total: u64, parts: usize) -> alloc::vec::Vec<u64> {
+        if parts == 0 {
+            assert!(total == 0, "rand_composition: no parts for a positive total");
+            return alloc::vec::Vec::new();
+        }
+        let bars = (parts - 1) as u64;
+        let slots = total.checked_add(bars).expect("rand_composition: overflow");
+        let mut positions = alloc::collections::BTreeSet::new();
+        for j in slots - bars..slots {
+            let t = self.rand_bounded_u64(j + 1);
+            if !positions.insert(t) {positions.insert(j);}
+        }
+        let mut a = alloc::vec::Vec::with_capacity(parts);
+        let mut start = 0;
+        for p in positions {
+            a.push(p - start);
+            start = p + 1;
+        }
+        a.push(slots - start);
+        a
+    }
+}
+
+/** A range of integers to sample from, see the macro `rand!`.
+
+Implemented for `a..b` and `a..=b` over `T` being `u32`, `u64`, `i32`, `i64`
+and `usize`. Sampling panics if the range is empty.
+*/
+pub trait SampleRange<T> {
+    /// A sample from the uniform distribution on the range.
+    fn sample<R: Rand>(self, rng: &mut R) -> T;
+}
+
+macro_rules! impl_sample_range {
+    ($t:ty, $range:ident, $range_inclusive:ident) => {
+        impl SampleRange<$t> for core::ops::Range<$t> {
+            fn sample<R: Rand>(self, rng: &mut R) -> $t {
+                assert!(self.start < self.end, "rand!: empty range");
+                rng.$range(self.start, self.end)
+            }
+        }
+        impl SampleRange<$t> for core::ops::RangeInclusive<$t> {
+            fn sample<R: Rand>(self, rng: &mut R) -> $t {
+                rng.$range_inclusive(*self.start(), *self.end())
+            }
+        }
+    }
+}
+
+impl_sample_range!(u32, rand_range_u32, rand_range_inclusive_u32);
+impl_sample_range!(u64, rand_range_u64, rand_range_inclusive_u64);
+impl_sample_range!(i32, rand_range_i32, rand_range_inclusive_i32);
+impl_sample_range!(i64, rand_range_i64, rand_range_inclusive_i64);
+
+impl SampleRange<usize> for core::ops::Range<usize> {
+    fn sample<R: Rand>(self, rng: &mut R) -> usize {
+        assert!(self.start < self.end, "rand!: empty range");
+        self.start + rng.rand_bounded_usize(self.end - self.start)
+    }
+}
+
+impl SampleRange<usize> for core::ops::RangeInclusive<usize> {
+    fn sample<R: Rand>(self, rng: &mut R) -> usize {
+        rng.rand_range_inclusive_u64(*self.start() as u64, *self.end() as u64) as usize
+    }
+}
+
+/** A sample from the uniform distribution on an integer range.
+
+The method is chosen by the type of the range, see `SampleRange`.
+Unsuffixed literals default to `i32`. The generator may be given
+by value or by mutable reference, as in `Rand::sample_range`.
+```
+use tiny_rng::{Rng, Rand, rand};
+
+let mut rng = Rng::from_seed(0);
+let die = rand!(rng, 1..=6);
+let index = rand!(rng, 0..10_usize);
+let x: u64 = rand!(rng, 0..1 << 40);
+assert!((1..=6).contains(&die) && index < 10 && x < 1 << 40);
+```
+*/
+#[macro_export]
+macro_rules! rand {
+    ($rng:expr, $range:expr) => {{
+        // By method call, so that `$rng` may be a generator as well
+        // as a mutable reference to one.
+        #[allow(unused_imports)]
+        use $crate::Rand as _;
+        $rng.sample_range($range)
+    }}
+}
+
+/** Seeding an engine with its full internal state.
+
+While `Rand::from_seed` takes a `u64` for every engine, the type of
+a full seed depends on the engine.
+
+```
+use tiny_rng::{Rng, Rand, SeedableEngine};
+
+let mut rng = Rng::from_full_seed((1, 2));
+assert_eq!(rng.rand_u64(), 0x800045);
+```
+*/
+pub trait SeedableEngine: Rand {
+    /// The full seed of the engine.
+    type Seed;
+
+    /// To obtain reproducible results from a full seed.
+    fn from_full_seed(seed: Self::Seed) -> Self;
+}
+
+/** An object safe subset of `Rand`, implemented for every engine.
+
+This permits to store different engines uniformly as
+`Box<dyn RandObj>`, which implements `Rand` in turn if
+feature `alloc` is enabled:
+```
+# #[cfg(feature = "alloc")] {
+use tiny_rng::{Rng, Rand, RandObj};
+
+let mut rng: Box<dyn RandObj> = Box::new(Rng::from_seed(0));
+println!("{}", rng.rand_range_u32(1, 7));
+# }
+```
+The method names differ from those of `Rand`, so that both traits
+may be in scope without ambiguity.
+*/
+pub trait RandObj {
+    /// A sample from the uniform distribution on `0..=u32::MAX`.
+    fn next_u32(&mut self) -> u32;
+
+    /// A sample from the uniform distribution on `0..=u64::MAX`.
+    fn next_u64(&mut self) -> u64;
+
+    /// Fill a buffer with random bytes.
+    fn fill_bytes(&mut self, a: &mut [u8]);
+
+    #[cfg(feature = "stats")]
+    #[doc(hidden)]
+    /// Forwards `Rand::record_rejection`.
+    /// Needs feature `stats` to be enabled.
+    fn record_rejection(&mut self);
+}
+
+impl<T: Rand> RandObj for T {
+    fn next_u32(&mut self) -> u32 {self.rand_u32()}
+    fn next_u64(&mut self) -> u64 {self.rand_u64()}
+    fn fill_bytes(&mut self, a: &mut [u8]) {self.fill(a)}
+
+    #[cfg(feature = "stats")]
+    fn record_rejection(&mut self) {Rand::record_rejection(self)}
+}
+
+#[cfg(feature = "alloc")]
+/// Needs feature `alloc` to be enabled.
+impl Rand for alloc::boxed::Box<dyn RandObj> {
+    /// Boxes the recommended engine `Rng`.
+    fn from_seed(seed: u64) -> Self {
+        alloc::boxed::Box::new(Rng::from_seed(seed))
+    }
+
+    fn rand_u32(&mut self) -> u32 {(**self).next_u32()}
+    fn rand_u64(&mut self) -> u64 {(**self).next_u64()}
+    fn fill(&mut self, a: &mut [u8]) {(**self).fill_bytes(a)}
+
+    #[cfg(feature = "stats")]
+    fn record_rejection(&mut self) {RandObj::record_rejection(&mut **self)}
+}
+
+/** A helper function to turn random number generation into an iterator.
+
+Example:
+```
+use tiny_rng::{Rng, Rand, rand_iter};
+
+let mut rng = Rng::from_seed(0);
+for x in rand_iter(&mut rng, Rand::rand_u32).take(10) {
+    println!("0x{:08x}", x);
+}
+```
+*/
+pub fn rand_iter<T: 'static, Generator: Rand>(
+    rng: &mut Generator,
+    rand: fn(&mut Generator) -> T
+) -> impl '_ + Iterator<Item = T>
+{
+    core::iter::from_fn(move || Some(rand(rng)))
+}
+
+#[cfg(feature = "alloc")]
+/// The iterator returned by `Rand::draw_indices`.
+/// Needs feature `alloc` to be enabled.
+pub struct DrawIndices<'a, Generator> {
+    rng: &'a mut Generator,
+    perm: alloc::vec::Vec<usize>,
+    pos: usize
+}
+
+#[cfg(feature = "alloc")]
+impl<Generator: Rand> Iterator for DrawIndices<'_, Generator> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let n = self.perm.len();
+        if self.pos == n {return None;}
+        let j = self.pos + self.rng.rand_bounded_usize(n - self.pos);
+        self.perm.swap(self.pos, j);
+        self.pos += 1;
+        Some(self.perm[self.pos - 1])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.perm.len() - self.pos;
+        (rest, Some(rest))
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// The swaps performed by `Rand::shuffle_recording`.
+/// Needs feature `alloc` to be enabled.
+#[derive(Clone, Debug)]
+pub struct ShuffleLog {
+    len: usize,
+    swaps: alloc::vec::Vec<usize>
+}
+
+#[cfg(feature = "alloc")]
+/** Reverse a shuffle recorded by `Rand::shuffle_recording`, by
+replaying the swaps in reverse order. The array must have the same
+length as the shuffled one.
+
+Needs feature `alloc` to be enabled.
+
+```
+use tiny_rng::{Rng, Rand, unshuffle};
+
+let mut rng = Rng::from_seed(0);
+let mut a = [1, 2, 3, 4];
+let log = rng.shuffle_recording(&mut a);
+unshuffle(&mut a, &log);
+assert_eq!(a, [1, 2, 3, 4]);
+```
+*/
+pub fn unshuffle<T>(a: &mut [T], log: &ShuffleLog) {
+    assert_eq!(a.len(), log.len, "unshuffle: length differs from the log");
+    let n = a.len();
+    for (k, &j) in log.swaps.iter().enumerate().rev() {
+        a.swap(n - 1 - k, j);
+    }
+}
+
+/** The uniform distribution on an integer range `a..b`.
+
+The mask needed for rejection sampling is computed once on
+construction, which pays off if many samples are drawn from the
+same range. The samples agree with those of `rand_range_u32`
+and `rand_range_u64`.
+
+```
+use tiny_rng::{Rng, Rand, UniformInt};
+
+let mut rng = Rng::from_seed(0);
+let dice = UniformInt::<u32>::new(1, 7);
+for _ in 0..10 {
+    println!("{}", dice.sample(&mut rng));
+}
+```
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct UniformInt<T> {
+    low: T,
+    range: T,
+    mask: T
+}
+
+impl UniformInt<u32> {
+    /// The uniform distribution on `a..b`. Needs `a < b`.
+    pub fn new(a: u32, b: u32) -> Self {
+        assert!(a < b, "UniformInt::new: empty range");
+        let range = b - a;
+        let mask = wrapping_next_power_of_two_u32(range).wrapping_sub(1);
+        Self {low: a, range, mask}
+    }
+
+    /// A sample from the distribution.
+    pub fn sample<Generator: Rand>(&self, rng: &mut Generator) -> u32 {
+        loop {
+            let x = self.mask & rng.rand_u32();
+            if x < self.range {return self.low + x;}
+            #[cfg(feature = "stats")]
+            rng.record_rejection();
+        }
+    }
+}
+
+impl UniformInt<u64> {
+    /// The uniform distribution on `a..b`. Needs `a < b`.
+    pub fn new(a: u64, b: u64) -> Self {
+        assert!(a < b, "UniformInt::new: empty range");
+        let range = b - a;
+        let mask = wrapping_next_power_of_two_u64(range).wrapping_sub(1);
+        Self {low: a, range, mask}
+    }
+
+    /// A sample from the distribution.
+    pub fn sample<Generator: Rand>(&self, rng: &mut Generator) -> u64 {
+        loop {
+            let x = self.mask & rng.rand_u64();
+            if x < self.range {return self.low + x;}
+            #[cfg(feature = "stats")]
+            rng.record_rejection();
+        }
+    }
+}
+
+/// The radical inverse of `n` in the given base, that is, the digits
+/// of `n` mirrored at the radix point.
+fn radical_inverse(mut n: u64, base: u32) -> f64 {
+    let b = base as u64;
+    let inv = 1.0/base as f64;
+    let mut factor = inv;
+    let mut y = 0.0;
+    while n > 0 {
+        y += (n % b) as f64*factor;
+        n /= b;
+        factor *= inv;
+    }
+    y
+}
+
+/** The Halton sequence in one dimension, also known as van der Corput
+sequence.
+
+Note: This sequence is not random. It is deterministic and has low
+discrepancy, which means that it covers the interval [0, 1) more
+evenly than a random sequence does. Such quasi-random sequences
+improve the rate of convergence of numerical integration. The
+sequence starts with index 1, thus `0.0` is skipped.
+
+```
+use tiny_rng::Halton;
+
+let mut h = Halton::new(2);
+assert_eq!([h.next(), h.next(), h.next()], [0.5, 0.25, 0.75]);
+```
+*/
+#[derive(Clone, Debug)]
+pub struct Halton {
+    base: u32,
+    index: u64
+}
+
+impl Halton {
+    /// The sequence to the given base, which must be at least 2.
+    /// The bases of different dimensions should be coprime.
+    pub fn new(base: u32) -> Self {
+        assert!(base >= 2, "Halton base must be at least 2");
+        Self {base, index: 0}
+    }
+
+    /// The next element of the sequence, a value in [0, 1).
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> f64 {
+        self.index += 1;
+        radical_inverse(self.index, self.base)
+    }
+}
+
+/** The Halton sequence in `D` dimensions.
+
+Each coordinate is a van der Corput sequence to its own base. As with
+`Halton`, the points are not random, but have low discrepancy.
+
+```
+use tiny_rng::HaltonND;
+
+let mut h = HaltonND::new([2, 3]);
+assert_eq!(h.next(), [0.5, 1.0/3.0]);
+```
+*/
+#[derive(Clone, Debug)]
+pub struct HaltonND<const D: usize> {
+    bases: [u32; D],
+    index: u64
+}
+
+impl<const D: usize> HaltonND<D> {
+    /// The sequence to the given bases, which must be at least 2
+    /// and should be pairwise coprime, for example the first `D` primes.
+    pub fn new(bases: [u32; D]) -> Self {
+        assert!(bases.iter().all(|&b| b >= 2), "Halton base must be at least 2");
+        Self {bases, index: 0}
+    }
+
+    /// The next point of the sequence, a value in [0, 1)^D.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> [f64; D] {
+        self.index += 1;
+        let mut x = [0.0; D];
+        for (xk, &base) in x.iter_mut().zip(&self.bases) {
+            *xk = radical_inverse(self.index, base);
+        }
+        x
+    }
+}
+
+/*
+Xorshift128+ is a modern algorithm for the fast generation of random
+numbers of relatively high quality, which passes the most important
+statistical tests. The generator is said to have a period length of
+2^128-1 and to pass "BigCrush" from the test suite "TestU01". It should
+be noted that this is by no means a cryptographic generator. The
+following implementation is as described in Vignas article [1], with
+the assignment a=23, b=17, c=26 described there as favorable, and is
+also listed that way in the Firefox source code [2] and in
+Wikipedia [3][4]. The internal state may be arbitrary,
+but not (0, 0).
+
+Since the low-order 32 bits of a random value of Xorshift128+ are not
+supposed to pass some statistical tests, it is better to extract
+the high-order 32 bits for rand_u32.
+
+[1] Sebastiano Vigna: "Further scramblings of Marsaglia's
+xorshift generators".
+Journal of Computational and Applied Mathematics (Mai 2017).
+arXiv:1404.0390. doi:10.1016/j.cam.2016.11.006.
+https://arxiv.org/abs/1404.0390
+
+[2] mfbt/XorShift128PlusRNG.h, retrieved 8 Feb. 2020.
+https://github.com/mozilla/gecko-dev/blob/master/mfbt/XorShift128PlusRNG.h
+-- Gecko is the current engine of Firefox.
+
+[3] Xorshift. English Wikipedia, retrieved 8 Feb. 2020.
+https://en.wikipedia.org/wiki/Xorshift
+
+[4] Xorshift. German Wikipedia, retrievend 8 Feb. 2020.
+https://de.wikipedia.org/wiki/Xorshift.
+*/
+
+/** Recommended engine. Currently Xorshift128+.
+
+The output stream of `Rng::from_seed` is part of the interface and
+is not changed within a major version. As a compatibility reference,
+the first eight outputs of `rand_u64` for the seeds 0 and 1 are:
+```
+use tiny_rng::{Rng, Rand};
+
+let mut rng = Rng::from_seed(0);
+assert_eq!([0; 8].map(|_| rng.rand_u64()), [
+    0x99a57b5e061d5cf4, 0xe17ca185389cd326,
+    0x16dbfb8f140c22a6, 0x8102fd9a4bf0caba,
+    0xa2990712ef161e34, 0xdcbfd4e34874ae7f,
+    0xc1af596d4967d508, 0x6f0b81e939fc465b
+]);
+
+let mut rng = Rng::from_seed(1);
+assert_eq!([0; 8].map(|_| rng.rand_u64()), [
+    0x99a57b5e069d5cb3, 0xe17ca185391cd2e5,
+    0x16dc3b8f138c12a4, 0x8102fd9a4b60ca79,
+    0xa299071aef022db3, 0xdcc01ceb4c70b037,
+    0xc5afa96547e5c4fc, 0x6f0989da3589c213
+]);
+```
+Downstream crates may copy these assertions into their own tests
+to pin the behavior of the dependency.
+*/
+#[derive(Clone)]
+pub struct Rng {
+    state: (u64, u64),
+    #[cfg(feature = "stats")]
+    rejections: u64
+}
+
+impl Rand for Rng {
+    fn from_seed(seed: u64) -> Self {
+        Self::with_state((
+            seed ^ 0xf4dbdf2183dcefb7, // [crc32(b"0"), crc32(b"1")]
+            seed ^ 0x1ad5be0d6dd28e9b  // [crc32(b"2"), crc32(b"3")]
+        ))
+    }
+
+    #[inline]
+    fn rand_u64(&mut self) -> u64 {
+        self.next_u64_raw()
+    }
+
+    #[inline]
+    fn rand_u32(&mut self) -> u32 {
+        (self.rand_u64() >> 32) as u32
+    }
+
+    #[cfg(feature = "stats")]
+    #[inline]
+    fn record_rejection(&mut self) {
+        self.rejections += 1;
+    }
+}
+
+/// Generators are equal if their internal states are equal.
+impl PartialEq for Rng {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl Eq for Rng {}
+
+/// Consistent with `PartialEq`, only the internal state is hashed.
+impl core::hash::Hash for Rng {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+    }
+}
+
+/// Properties of an engine, see `Rng::engine_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EngineInfo {
+    /// The name of the algorithm.
+    pub name: &'static str,
+    /// The period is about 2^period_bits.
+    pub period_bits: u32,
+    /// The size of the internal state.
+    pub state_bytes: usize
+}
+
+impl SeedableEngine for Rng {
+    /// The internal state, which may be arbitrary, but not `(0, 0)`.
+    type Seed = (u64, u64);
+
+    fn from_full_seed(seed: (u64, u64)) -> Self {
+        Self::with_state(seed)
+    }
+}
+
+/// The Xorshift128+ state may be arbitrary, but not `(0, 0)`,
+/// which is a fixed point.
+fn validate_state(state: (u64, u64)) -> bool {
+    state != (0, 0)
+}
+
+impl Rng {
+    // Every constructor passes through here.
+    fn with_state(state: (u64, u64)) -> Self {
+        assert!(validate_state(state), "Rng: the state (0, 0) is forbidden");
+        Self {
+            state,
+            #[cfg(feature = "stats")]
+            rejections: 0
+        }
+    }
+
+    /// Like `Rand::from_seed`, but the first `warmup` outputs are
+    /// discarded. Xorshift128+ does not need this, yet it makes the
+    /// streams of adjacent seeds differ right from the first output.
+    pub fn from_seed_warmed(seed: u64, warmup: u32) -> Self {
+        let mut rng = Self::from_seed(seed);
+        for _ in 0..warmup {rng.rand_u64();}
+        rng
+    }
+
+    /// A generator from a seed of little entropy, such as a single
+    /// noisy sensor reading. The seed is passed through several rounds
+    /// of SplitMix64, so that even adjacent seeds give well separated
+    /// streams. This improves the dispersion of seeds, but does not
+    /// add entropy: there are still only as many streams as seeds.
+    pub fn from_low_entropy(seed: u64) -> Self {
+        let mut x = seed;
+        for _ in 0..4 {x = splitmix64(x);}
+        // SplitMix64 is a bijection with splitmix64(0) != 0,
+        // thus the state cannot be (0, 0).
+        Self::with_state((x, splitmix64(x)))
+    }
+
+    /// The name of the engine.
+    pub const NAME: &'static str = "Xorshift128+";
+
+    /// The period is 2^128 - 1.
+    pub const PERIOD_BITS: u32 = 128;
+
+    /// The size of the internal state.
+    pub const STATE_BYTES: usize = 16;
+
+    /// The properties of the engine, for logging.
+    pub const fn engine_info() -> EngineInfo {
+        EngineInfo {
+            name: Self::NAME,
+            period_bits: Self::PERIOD_BITS,
+            state_bytes: Self::STATE_BYTES
+        }
+    }
+
+    /// The Xorshift128+ step, equivalent to `rand_u64`. As an inherent
+    /// method that is always inlined, it suits hot loops best.
+    #[inline(always)]
+    pub fn next_u64_raw(&mut self) -> u64 {
+        xorshift128plus_step(&mut self.state)
+    }
+
+    /// The value the next call of `rand_u64` will return,
+    /// without advancing the generator.
+    pub fn peek_u64(&self) -> u64 {
+        let mut state = self.state;
+        xorshift128plus_step(&mut state)
+    }
+
+    #[cfg(feature = "stats")]
+    /// The number of draws rejected so far by `rand_bounded_u32`,
+    /// `rand_bounded_u64` and `UniformInt::sample`, also through a
+    /// `Box<dyn RandObj>`. This helps to judge the overhead of
+    /// rejection sampling in a workload.
+    /// Needs feature `stats` to be enabled.
+    pub fn rejection_count(&self) -> u64 {
+        self.rejections
+    }
+
+    /** A helper function to turn random number generation into an iterator.
+
+    Example:
+    ```
+    use tiny_rng::{Rng, Rand};
+
+    let mut rng = Rng::from_seed(0);
+    for x in rng.iter(Rand::rand_u32).take(10) {
+        println!("0x{:08x}", x);
     }
     ```
     */
     pub fn iter<T: 'static>(&mut self, rand: fn(&mut Self) -> T)
     -> impl '_ + Iterator<Item = T>
     {
-        rand_iter(self, rand)
+        rand_iter(self, rand)
+    }
+
+    /** Like `iter`, but the function also receives the index
+    of the produced item.
+
+    Example, increasing keys with random gaps:
+    ```
+    use tiny_rng::{Rng, Rand};
+
+    let mut rng = Rng::from_seed(0);
+    let keys: Vec<u32> = rng.enumerated_iter(|rng, i| 10*i as u32 + rng.rand_bounded_u32(10))
+        .take(4).collect();
+    ```
+    */
+    pub fn enumerated_iter<T: 'static>(&mut self, f: fn(&mut Self, usize) -> T)
+    -> impl '_ + Iterator<Item = T>
+    {
+        let mut index = 0;
+        core::iter::from_fn(move || {
+            let x = f(self, index);
+            index += 1;
+            Some(x)
+        })
+    }
+
+    /** Like `iter`, but the iterator ends after the first value
+    for which `pred` holds, which is still yielded.
+
+    ```
+    use tiny_rng::{Rng, Rand};
+
+    let mut rng = Rng::from_seed(0);
+    let rolls: Vec<u32> = rng.iter_until(|rng| rng.rand_range_u32(1, 7), |&x| x == 6)
+        .collect();
+    assert_eq!(rolls.last(), Some(&6));
+    ```
+    */
+    pub fn iter_until<T: 'static>(&mut self, f: fn(&mut Self) -> T, pred: fn(&T) -> bool)
+    -> impl '_ + Iterator<Item = T>
+    {
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {return None;}
+            let x = f(self);
+            done = pred(&x);
+            Some(x)
+        })
+    }
+
+    /// An iterator of samples from the uniform distribution
+    /// on `0..=u32::MAX`.
+    pub fn u32_iter(&mut self) -> RandU32Iter<'_> {
+        RandU32Iter {rng: self}
+    }
+
+    /** An iterator of samples from the uniform distribution
+    on `0..=u64::MAX`.
+
+    Example:
+    ```
+    use tiny_rng::{Rng, Rand};
+
+    let mut rng = Rng::from_seed(0);
+    let a: Vec<u64> = rng.u64_iter().take(4).collect();
+    ```
+    */
+    pub fn u64_iter(&mut self) -> RandU64Iter<'_> {
+        RandU64Iter {rng: self}
+    }
+
+    #[cfg(feature = "simd")]
+    /// Fill a buffer with random bytes, faster than `fill` for large
+    /// buffers. Four independent Xorshift128+ lanes, seeded from this
+    /// generator, run in parallel and their outputs are interleaved.
+    /// On x86_64, the lanes run on SSE2, two to a 128-bit register,
+    /// otherwise on scalar code. Both give the same bytes.
+    ///
+    /// Note: This is a distinct generator, its byte sequence differs
+    /// from that of `fill`. Needs feature `simd` to be enabled.
+    pub fn fill_simd(&mut self, buf: &mut [u8]) {
+        let mut s0 = [0; 4];
+        let mut s1 = [0; 4];
+        for k in 0..4 {
+            // The state of a lane must not be (0, 0).
+            s0[k] = self.rand_u64();
+            s1[k] = self.rand_u64() | 1;
+        }
+        #[cfg(target_arch = "x86_64")]
+        fill_lanes_sse2(s0, s1, buf);
+        #[cfg(not(target_arch = "x86_64"))]
+        fill_lanes_scalar(s0, s1, buf);
+    }
+}
+
+/// Advance a Xorshift128+ state and return the output.
+#[inline(always)]
+fn xorshift128plus_step(state: &mut (u64, u64)) -> u64 {
+    let (mut x, y) = *state;
+    state.0 = y;
+    x ^= x << 23;
+    state.1 = x ^ y ^ (x >> 17) ^ (y >> 26);
+    state.1.wrapping_add(y)
+}
+
+#[cfg(feature = "simd")]
+/// Fill a buffer with the interleaved outputs of four Xorshift128+
+/// lanes with the states `(s0[k], s1[k])`, see `Rng::fill_simd`.
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+fn fill_lanes_scalar(mut s0: [u64; 4], mut s1: [u64; 4], buf: &mut [u8]) {
+    let mut next_block = |block: &mut [u8]| {
+        let mut out = [0u64; 4];
+        for k in 0..4 {
+            let mut state = (s0[k], s1[k]);
+            out[k] = xorshift128plus_step(&mut state);
+            s0[k] = state.0;
+            s1[k] = state.1;
+        }
+        for (dest, x) in block.chunks_exact_mut(8).zip(&out) {
+            dest.copy_from_slice(&x.to_le_bytes());
+        }
+    };
+    let mut chunks = buf.chunks_exact_mut(32);
+    for chunk in &mut chunks {
+        next_block(chunk);
+    }
+    let rest = chunks.into_remainder();
+    if !rest.is_empty() {
+        let mut block = [0; 32];
+        next_block(&mut block);
+        rest.copy_from_slice(&block[..rest.len()]);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+/// Like `fill_lanes_scalar`, but the lanes are held in two SSE2
+/// registers. SSE2 is part of the x86_64 baseline, so no runtime
+/// detection is needed.
+// The intrinsics are safe to call since Rust 1.87 where SSE2 is
+// enabled, but unsafe before.
+#[allow(unused_unsafe)]
+fn fill_lanes_sse2(s0: [u64; 4], s1: [u64; 4], buf: &mut [u8]) {
+    use core::arch::x86_64::{
+        __m128i, _mm_add_epi64, _mm_set_epi64x, _mm_slli_epi64,
+        _mm_srli_epi64, _mm_storeu_si128, _mm_xor_si128
+    };
+    #[inline(always)]
+    fn step(s0: &mut __m128i, s1: &mut __m128i) -> __m128i {
+        unsafe {
+            let (mut x, y) = (*s0, *s1);
+            *s0 = y;
+            x = _mm_xor_si128(x, _mm_slli_epi64(x, 23));
+            *s1 = _mm_xor_si128(_mm_xor_si128(x, y),
+                _mm_xor_si128(_mm_srli_epi64(x, 17), _mm_srli_epi64(y, 26)));
+            _mm_add_epi64(*s1, y)
+        }
+    }
+    // Lanes 0 and 1 in the first register, 2 and 3 in the second,
+    // the lower lane in the lower half.
+    let (mut a0, mut a1, mut b0, mut b1) = unsafe {(
+        _mm_set_epi64x(s0[1] as i64, s0[0] as i64),
+        _mm_set_epi64x(s1[1] as i64, s1[0] as i64),
+        _mm_set_epi64x(s0[3] as i64, s0[2] as i64),
+        _mm_set_epi64x(s1[3] as i64, s1[2] as i64)
+    )};
+    let mut next_block = |block: &mut [u8]| {
+        debug_assert!(block.len() == 32);
+        let (x, y) = (step(&mut a0, &mut a1), step(&mut b0, &mut b1));
+        // x86_64 is little-endian, so the bytes agree with the scalar
+        // `to_le_bytes`. The stores are unaligned and stay within the
+        // 32 bytes of the block.
+        unsafe {
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, x);
+            _mm_storeu_si128(block.as_mut_ptr().add(16) as *mut __m128i, y);
+        }
+    };
+    let mut chunks = buf.chunks_exact_mut(32);
+    for chunk in &mut chunks {
+        next_block(chunk);
+    }
+    let rest = chunks.into_remainder();
+    if !rest.is_empty() {
+        let mut block = [0; 32];
+        next_block(&mut block);
+        rest.copy_from_slice(&block[..rest.len()]);
+    }
+}
+
+/// The iterator returned by `Rng::u32_iter`.
+pub struct RandU32Iter<'a> {
+    rng: &'a mut Rng
+}
+
+impl Iterator for RandU32Iter<'_> {
+    type Item = u32;
+
+    #[inline]
+    fn next(&mut self) -> Option<u32> {
+        Some(self.rng.rand_u32())
+    }
+}
+
+/// The iterator returned by `Rng::u64_iter`.
+pub struct RandU64Iter<'a> {
+    rng: &'a mut Rng
+}
+
+impl Iterator for RandU64Iter<'_> {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        Some(self.rng.rand_u64())
+    }
+}
+
+#[cfg(feature = "std")]
+/** Streaming statistics of a sequence of values, by Welford's
+numerically stable algorithm. Useful to check the distribution
+of generated samples.
+
+Needs feature `std` to be enabled.
+
+```
+use tiny_rng::{Rng, Rand, Welford};
+
+let mut rng = Rng::from_seed(0);
+let mut stats = Welford::new();
+for _ in 0..1000 {
+    stats.push(rng.rand_f64());
+}
+assert!((stats.mean() - 0.5).abs() < 0.05);
+```
+*/
+#[derive(Clone, Debug)]
+pub struct Welford {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64
+}
+
+#[cfg(feature = "std")]
+impl Welford {
+    /// An empty accumulator.
+    pub fn new() -> Self {
+        Self {count: 0, mean: 0.0, m2: 0.0,
+            min: f64::INFINITY, max: f64::NEG_INFINITY}
+    }
+
+    /// Add a value.
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta/self.count as f64;
+        self.m2 += delta*(x - self.mean);
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+    }
+
+    /// The number of values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The arithmetic mean, NaN if empty.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {f64::NAN} else {self.mean}
+    }
+
+    /// The sample variance, with denominator `count - 1`.
+    /// NaN for fewer than two values.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {f64::NAN} else {self.m2/(self.count - 1) as f64}
+    }
+
+    /// The smallest value, infinity if empty.
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+
+    /// The largest value, negative infinity if empty.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Welford {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+/** Weighted sampling of indices, where weights may be updated between
+draws. Backed by a Fenwick tree, both `update` and `sample` take
+logarithmic time.
+
+Needs feature `alloc` to be enabled.
+
+```
+use tiny_rng::{Rng, Rand, FenwickSampler};
+
+let mut rng = Rng::from_seed(0);
+let mut sampler = FenwickSampler::new(&[1.0, 2.0, 3.0]);
+sampler.update(0, 0.0);
+assert_ne!(sampler.sample(&mut rng), 0);
+```
+*/
+#[derive(Clone, Debug)]
+pub struct FenwickSampler {
+    weights: alloc::vec::Vec<f64>,
+    tree: alloc::vec::Vec<f64>
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickSampler {
+    /// A sampler with the given initial weights, which must be
+    /// finite and non-negative.
+    pub fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        let mut sampler = Self {
+            weights: alloc::vec![0.0; n],
+            tree: alloc::vec![0.0; n + 1]
+        };
+        for (i, &w) in weights.iter().enumerate() {
+            sampler.update(i, w);
+        }
+        sampler
+    }
+
+    /// The number of weights.
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Whether there are no weights.
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// The current weight of an index.
+    pub fn weight(&self, index: usize) -> f64 {
+        self.weights[index]
+    }
+
+    /// The sum of all weights.
+    pub fn total(&self) -> f64 {
+        let mut i = self.weights.len();
+        let mut sum = 0.0;
+        while i > 0 {
+            sum += self.tree[i];
+            i &= i - 1;
+        }
+        sum
+    }
+
+    /// Set the weight of an index, which must be finite and non-negative.
+    pub fn update(&mut self, index: usize, weight: f64) {
+        assert!(weight >= 0.0 && weight.is_finite(), "FenwickSampler: invalid weight");
+        let delta = weight - self.weights[index];
+        self.weights[index] = weight;
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// A random index, chosen with probability proportional to its
+    /// weight. The weights must not all be zero.
+    pub fn sample<Generator: Rand>(&self, rng: &mut Generator) -> usize {
+        let total = self.total();
+        assert!(total > 0.0, "FenwickSampler: total weight is zero");
+        let n = self.weights.len();
+        let mut x = rng.rand_f64()*total;
+        let mut pos = 0;
+        let mut step = (n + 1).next_power_of_two() >> 1;
+        while step > 0 {
+            if pos + step <= n && self.tree[pos + step] <= x {
+                pos += step;
+                x -= self.tree[pos];
+            }
+            step >>= 1;
+        }
+        if pos < n && self.weights[pos] > 0.0 {
+            pos
+        } else {
+            // Reached only by rounding, take the nearest possible index.
+            self.weights[..pos.min(n)].iter().rposition(|&w| w > 0.0)
+                .or_else(|| self.weights.iter().position(|&w| w > 0.0))
+                .unwrap()
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+/** An approximate shuffle of a stream with bounded memory.
+
+Items are collected into a buffer of fixed capacity. Once it is full,
+each pushed item replaces a randomly chosen buffered one, which is
+emitted. The larger the capacity, the better the mixing.
+
+Needs feature `alloc` to be enabled.
+
+```
+use tiny_rng::{Rng, Rand, ShuffleBuffer};
+
+let mut rng = Rng::from_seed(0);
+let mut buffer = ShuffleBuffer::new(4);
+let mut out = Vec::new();
+for x in 0..10 {
+    out.extend(buffer.push(x, &mut rng));
+}
+out.extend(buffer.drain(&mut rng));
+assert_eq!(out.len(), 10);
+```
+*/
+#[derive(Clone, Debug)]
+pub struct ShuffleBuffer<T> {
+    buffer: alloc::vec::Vec<T>,
+    capacity: usize
+}
+
+#[cfg(feature = "alloc")]
+impl<T> ShuffleBuffer<T> {
+    /// An empty buffer of the given capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self {buffer: alloc::vec::Vec::with_capacity(capacity), capacity}
+    }
+
+    /// Add an item. Returns a random buffered item once the buffer
+    /// is full, otherwise `None`.
+    pub fn push<Generator: Rand>(&mut self, item: T, rng: &mut Generator) -> Option<T> {
+        if self.buffer.len() < self.capacity {
+            self.buffer.push(item);
+            return None;
+        }
+        if self.capacity == 0 {return Some(item);}
+        let j = rng.rand_bounded_usize(self.capacity);
+        Some(core::mem::replace(&mut self.buffer[j], item))
+    }
+
+    /// Emit the remaining items in random order.
+    pub fn drain<Generator: Rand>(&mut self, rng: &mut Generator)
+    -> alloc::vec::Drain<'_, T>
+    {
+        rng.shuffle(&mut self.buffer);
+        self.buffer.drain(..)
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// A sampler of a component of a `Mixture`.
+/// Needs feature `alloc` to be enabled.
+pub type BoxedSampler = alloc::boxed::Box<dyn Fn(&mut Rng) -> f64>;
+
+#[cfg(feature = "alloc")]
+/** A mixture of distributions: a component is chosen with
+probability proportional to its weight, then sampled from.
+
+Needs feature `alloc` to be enabled.
+
+```
+# #[cfg(feature = "std")] {
+use tiny_rng::{Rng, Rand, Mixture};
+
+let mut rng = Rng::from_seed(0);
+let bimodal = Mixture::new(vec![
+    (0.3, Box::new(|rng: &mut Rng| rng.rand_normal_f64(-2.0, 0.5))),
+    (0.7, Box::new(|rng: &mut Rng| rng.rand_normal_f64(2.0, 1.0)))
+]);
+let x = bimodal.sample(&mut rng);
+# }
+```
+*/
+pub struct Mixture {
+    components: alloc::vec::Vec<(f64, BoxedSampler)>,
+    total: f64
+}
+
+#[cfg(feature = "alloc")]
+impl Mixture {
+    /// A mixture of the given `(weight, sampler)` components. The
+    /// weights must be finite and non-negative, and not all zero.
+    pub fn new(components: alloc::vec::Vec<(f64, BoxedSampler)>) -> Self {
+        assert!(components.iter().all(|c| c.0 >= 0.0 && c.0.is_finite()),
+            "Mixture: invalid weight");
+        let total: f64 = components.iter().map(|c| c.0).sum();
+        assert!(total > 0.0, "Mixture: total weight is zero");
+        Self {components, total}
+    }
+
+    /// A sample from the mixture.
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        let x = rng.rand_f64()*self.total;
+        let i = weighted_index(x, self.components.iter().map(|c| c.0));
+        (self.components[i].1)(rng)
+    }
+}
+
+/** Mix two 64-bit values into a seed, via the SplitMix64 finalizer.
+
+As a `const fn`, it can derive seeds at compile time:
+```
+use tiny_rng::{Rand, Rng};
+const SEED: u64 = tiny_rng::mix_seed(0x6d6f64756c65, 1);
+let mut rng = Rng::from_seed(SEED);
+```
+*/
+pub const fn mix_seed(a: u64, b: u64) -> u64 {
+    splitmix64(a ^ splitmix64(b))
+}
+
+/// The expected number of rejected draws of `rand_bounded_u64(m)`,
+/// and likewise of `rand_bounded_u32` for bounds that fit. With `p`
+/// the next power of two `p >= m`, this is `(p - m)/m`, which is zero
+/// for powers of two and below one otherwise. The bound `m = 0` never
+/// terminates, for which infinity is returned.
+pub fn expected_rejections(m: u64) -> f64 {
+    if m == 0 {return f64::INFINITY;}
+    // Exact in integers, as 2^64 wraps to 0. Since d < m, the quotient
+    // is below one, but may round to one for m close to 2^64, in which
+    // case the next smaller float is taken.
+    let d = wrapping_next_power_of_two_u64(m).wrapping_sub(m);
+    (d as f64/m as f64).min(1.0 - f64::EPSILON/2.0)
+}
+
+/** Fill a buffer with the random bytes of `Rng::from_seed(seed)`.
+
+This is convenient for deterministic test fixtures:
+```
+let mut a = [0; 16];
+tiny_rng::fill_from_seed(0, &mut a);
+```
+*/
+pub fn fill_from_seed(seed: u64, buf: &mut [u8]) {
+    Rng::from_seed(seed).fill(buf);
+}
+
+/// Shuffle an array by `Rng::from_seed(seed)`. For the same seed and
+/// length, the permutation is the same, which allows independent
+/// processes to shuffle identically without sharing a generator.
+pub fn shuffle_seeded<T>(seed: u64, a: &mut [T]) {
+    Rng::from_seed(seed).shuffle(a);
+}
+
+/// FNV-1a, with the output passed through the SplitMix64 finalizer.
+/// Integers are hashed as little-endian and `usize` as `u64`, so the
+/// result does not depend on the target.
+struct SeedHasher(u64);
+
+impl core::hash::Hasher for SeedHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+    fn write_u16(&mut self, x: u16) {self.write(&x.to_le_bytes());}
+    fn write_u32(&mut self, x: u32) {self.write(&x.to_le_bytes());}
+    fn write_u64(&mut self, x: u64) {self.write(&x.to_le_bytes());}
+    fn write_u128(&mut self, x: u128) {self.write(&x.to_le_bytes());}
+    fn write_usize(&mut self, x: usize) {self.write_u64(x as u64);}
+    fn write_i16(&mut self, x: i16) {self.write_u16(x as u16);}
+    fn write_i32(&mut self, x: i32) {self.write_u32(x as u32);}
+    fn write_i64(&mut self, x: i64) {self.write_u64(x as u64);}
+    fn write_i128(&mut self, x: i128) {self.write_u128(x as u128);}
+    fn write_isize(&mut self, x: isize) {self.write_u64(x as i64 as u64);}
+    fn finish(&self) -> u64 {splitmix64(self.0)}
+}
+
+/// Shuffle an array by a seed derived from hashing `key`. The same key
+/// and length always give the same permutation, across runs and
+/// targets, which allows partitioning data by key deterministically.
+pub fn shuffle_keyed<T, K: core::hash::Hash + ?Sized>(key: &K, a: &mut [T]) {
+    use core::hash::Hasher;
+    let mut h = SeedHasher(0xcbf29ce484222325);
+    key.hash(&mut h);
+    Rng::from_seed(h.finish()).shuffle_portable(a);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Rng, Rand, SeedableEngine, UniformInt, Halton, HaltonND};
+
+    fn rng_test<Generator: Rand>() {
+        let mut rng = Generator::from_seed(0);
+        println!("{:?}", rng.rand_range_u32(1, 6));
+    }
+
+    #[test]
+    fn test0() {
+        rng_test::<Rng>();
+    }
+
+    #[test]
+    fn uniform_int_agrees_with_rand_range() {
+        let mut rng1 = Rng::from_seed(0);
+        let mut rng2 = Rng::from_seed(0);
+        for &(a, b) in &[(0, 1), (1, 7), (10, 62), (0, 1 << 31), (5, u32::MAX)] {
+            let dist = UniformInt::<u32>::new(a, b);
+            for _ in 0..1000 {
+                assert_eq!(dist.sample(&mut rng1), rng2.rand_range_u32(a, b));
+            }
+        }
+        let dist = UniformInt::<u64>::new(3, 1000);
+        for _ in 0..1000 {
+            assert_eq!(dist.sample(&mut rng1), rng2.rand_range_u64(3, 1000));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "UniformInt::new: empty range")]
+    fn uniform_int_empty() {
+        UniformInt::<u32>::new(5, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "UniformInt::new: empty range")]
+    fn uniform_int_reversed() {
+        UniformInt::<u64>::new(6, 5);
+    }
+
+    #[test]
+    fn antithetic_pair_sums_to_one() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..10000 {
+            let (u, v) = rng.rand_f64_antithetic();
+            assert!((0.0..1.0).contains(&u));
+            assert_eq!(u + v, 1.0);
+        }
+    }
+
+    #[test]
+    fn halton_van_der_corput() {
+        let mut h = Halton::new(2);
+        let expected = [0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875, 0.0625];
+        for &y in &expected {
+            assert_eq!(h.next(), y);
+        }
+        let mut h = Halton::new(3);
+        let expected = [1.0/3.0, 2.0/3.0, 1.0/9.0, 4.0/9.0, 7.0/9.0, 2.0/9.0];
+        for &y in &expected {
+            assert!((h.next() - y).abs() < 1E-15);
+        }
+        let mut h = HaltonND::new([2, 3]);
+        let mut h2 = Halton::new(2);
+        let mut h3 = Halton::new(3);
+        for _ in 0..100 {
+            assert_eq!(h.next(), [h2.next(), h3.next()]);
+        }
+    }
+
+    #[test]
+    fn range_step() {
+        let mut rng = Rng::from_seed(0);
+        let mut hits = [0; 20];
+        for _ in 0..10000 {
+            let x = rng.rand_range_step_u32(0, 100, 5);
+            assert!(x < 100 && x % 5 == 0);
+            hits[(x/5) as usize] += 1;
+        }
+        assert!(hits.iter().all(|&h| h > 0));
+        for _ in 0..1000 {
+            let x = rng.rand_range_step_u32(3, 11, 4);
+            assert!(x == 3 || x == 7);
+        }
+        assert_eq!(rng.rand_range_step_u32(7, 8, u32::MAX), 7);
+        assert!(rng.rand_range_step_u32(0, u32::MAX, u32::MAX/2) < u32::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "rand_range_step_u32: step must be positive")]
+    fn range_step_zero() {
+        Rng::from_seed(0).rand_range_step_u32(0, 10, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "rand_range_step_u32: empty range")]
+    fn range_step_empty() {
+        Rng::from_seed(0).rand_range_step_u32(10, 10, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "rand_range_step_u32: empty range")]
+    fn range_step_reversed() {
+        Rng::from_seed(0).rand_range_step_u32(10, 0, 2);
+    }
+
+    #[test]
+    fn shuffle_pair_keeps_correspondence() {
+        let mut rng = Rng::from_seed(0);
+        let mut a: Vec<u32> = (0..100).collect();
+        let mut b: Vec<u32> = (0..100).map(|x| 2*x).collect();
+        rng.shuffle_pair(&mut a, &mut b);
+        assert!(a.iter().zip(&b).all(|(x, y)| 2*x == *y));
+        assert!(a.iter().enumerate().any(|(i, &x)| i as u32 != x));
+
+        let mut rng2 = Rng::from_seed(0);
+        let mut c: Vec<u32> = (0..100).collect();
+        rng2.shuffle(&mut c);
+        assert_eq!(a, c);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rand_bytes() {
+        let mut rng = Rng::from_seed(0);
+        assert!(rng.rand_bytes(0).is_empty());
+        let a = rng.rand_bytes(100000);
+        let b = rng.rand_bytes(100000);
+        assert_eq!(a.len(), 100000);
+        assert_ne!(a, b);
+        let mut c = vec![0; 100000];
+        Rng::from_seed(0).fill(&mut c);
+        assert_eq!(Rng::from_seed(0).rand_bytes(100000), c);
+    }
+
+    #[test]
+    fn iter_structs() {
+        let mut rng = Rng::from_seed(0);
+        let a: Vec<u64> = rng.u64_iter().take(10).collect();
+        let b: Vec<u32> = rng.u32_iter().take(10).collect();
+        let mut rng = Rng::from_seed(0);
+        assert!(a.iter().all(|&x| x == rng.rand_u64()));
+        assert!(b.iter().all(|&x| x == rng.rand_u32()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn f_distribution_mean() {
+        let mut rng = Rng::from_seed(0);
+        let n = 100000;
+        let k = 3.0;
+        let mean = (0..n).map(|_| rng.rand_chi_squared_f64(k)).sum::<f64>()/n as f64;
+        assert!((mean - k).abs() < 0.05);
+        let (d1, d2) = (5.0, 50.0);
+        let mean = (0..n).map(|_| rng.rand_f_distribution_f64(d1, d2))
+            .sum::<f64>()/n as f64;
+        assert!((mean - d2/(d2 - 2.0)).abs() < 0.02);
+    }
+
+    #[test]
+    fn rotate_random() {
+        let mut rng = Rng::from_seed(0);
+        let mut empty: [u32; 0] = [];
+        rng.rotate_random(&mut empty);
+        let mut seen = [false; 8];
+        for _ in 0..1000 {
+            let mut a = [0, 1, 2, 3, 4, 5, 6, 7];
+            rng.rotate_random(&mut a);
+            let k = a[0];
+            assert!((0..8).all(|i| a[i] == (i + k) % 8));
+            seen[k] = true;
+        }
+        assert!(seen.iter().all(|&x| x));
+    }
+
+    #[test]
+    fn golden_output() {
+        let mut rng = Rng::from_seed(0);
+        assert_eq!(rng.rand_u64(), 0x99a57b5e061d5cf4);
+        assert_eq!(rng.rand_u32(), 0xe17ca185);
+        let mut rng = Rng::from_seed(1);
+        assert_eq!(rng.rand_u64(), 0x99a57b5e069d5cb3);
+        assert_eq!(rng.rand_u32(), 0xe17ca185);
+    }
+
+    #[test]
+    fn range_signed_wide() {
+        let mut rng = Rng::from_seed(0);
+        for &(a, b) in &[(-10, 10), (-2_000_000_000, 2_000_000_000),
+            (i32::MIN, i32::MAX), (i32::MIN, 0), (0, i32::MAX), (-1, 0)]
+        {
+            for _ in 0..1000 {
+                let x = rng.rand_range_i32(a, b);
+                assert!(a <= x && x < b);
+            }
+        }
+        assert!((0..1000).any(|_| rng.rand_range_i32(-2_000_000_000, 2_000_000_000) > 0));
+        assert!((0..1000).any(|_| rng.rand_range_i32(-2_000_000_000, 2_000_000_000) < 0));
+        for &(a, b) in &[(-10, 10), (i64::MIN, i64::MAX), (i64::MIN/2 - 1, i64::MAX/2 + 1)] {
+            for _ in 0..1000 {
+                let x = rng.rand_range_i64(a, b);
+                assert!(a <= x && x < b);
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn distinct_u64() {
+        let mut rng = Rng::from_seed(0);
+        assert!(rng.distinct_u64(0).is_empty());
+        let a = rng.distinct_u64(10000);
+        assert_eq!(a.len(), 10000);
+        let set: std::collections::HashSet<u64> = a.iter().copied().collect();
+        assert_eq!(set.len(), a.len());
+    }
+
+    #[test]
+    fn bounded_power_of_two() {
+        let mut rng1 = Rng::from_seed(0);
+        let mut rng2 = Rng::from_seed(0);
+        let mut hist = [0; 256];
+        for _ in 0..25600 {
+            let x = rng1.rand_bounded_u32(256);
+            let mask = 255;
+            assert_eq!(x, mask & rng2.rand_u32());
+            hist[x as usize] += 1;
+        }
+        assert!(hist.iter().all(|&h| 50 < h && h < 150));
+        for _ in 0..100 {
+            assert_eq!(rng1.rand_bounded_u32(1), 0);
+            assert_eq!(rng1.rand_bounded_u32(1 << 31) >> 31, 0);
+        }
+    }
+
+    #[test]
+    fn colors() {
+        let mut rng = Rng::from_seed(0);
+        let mut seen = [[false; 256]; 3];
+        for _ in 0..10000 {
+            let (r, g, b) = rng.rand_rgb();
+            seen[0][r as usize] = true;
+            seen[1][g as usize] = true;
+            seen[2][b as usize] = true;
+        }
+        assert!(seen.iter().all(|c| c.iter().all(|&x| x)));
+        for _ in 0..1000 {
+            let (h, s, v) = rng.rand_hsv_pleasing();
+            assert!((0.0..1.0).contains(&h));
+            assert!((0.5..0.8).contains(&s));
+            assert!((0.85..1.0).contains(&v));
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn rejection_count() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..1000 {
+            rng.rand_bounded_u32(256);
+            rng.rand_bounded_u64(1 << 40);
+        }
+        assert_eq!(rng.rejection_count(), 0);
+        for _ in 0..1000 {
+            rng.rand_bounded_u32(257);
+        }
+        let count = rng.rejection_count();
+        assert!(count > 500);
+        for _ in 0..1000 {
+            rng.rand_bounded_u64((1 << 40) + 1);
+        }
+        assert!(rng.rejection_count() > count + 500);
+        let count = rng.rejection_count();
+        let d = UniformInt::<u32>::new(0, 257);
+        for _ in 0..1000 {d.sample(&mut rng);}
+        let d = UniformInt::<u64>::new(0, (1 << 40) + 1);
+        for _ in 0..1000 {d.sample(&mut rng);}
+        assert!(rng.rejection_count() > count + 1000);
+    }
+
+    #[cfg(all(feature = "stats", feature = "alloc"))]
+    #[test]
+    fn rejection_count_boxed() {
+        use std::{rc::Rc, cell::Cell};
+        // Counts the rejections forwarded to the boxed generator.
+        struct Counting(Rng, Rc<Cell<u64>>);
+        impl Rand for Counting {
+            fn from_seed(seed: u64) -> Self {Self(Rng::from_seed(seed), Rc::default())}
+            fn rand_u32(&mut self) -> u32 {self.0.rand_u32()}
+            fn rand_u64(&mut self) -> u64 {self.0.rand_u64()}
+            fn record_rejection(&mut self) {self.1.set(self.1.get() + 1);}
+        }
+        let count = Rc::new(Cell::new(0));
+        let mut rng: alloc::boxed::Box<dyn crate::RandObj> =
+            alloc::boxed::Box::new(Counting(Rng::from_seed(0), count.clone()));
+        for _ in 0..1000 {rng.rand_bounded_u32(257);}
+        let c = count.get();
+        assert!(c > 500);
+        let d = UniformInt::<u32>::new(0, 257);
+        for _ in 0..1000 {d.sample(&mut rng);}
+        assert!(count.get() > c + 500);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn shuffle_tracked() {
+        let mut rng = Rng::from_seed(0);
+        let old: Vec<u32> = (0..50).map(|x| x*x).collect();
+        let mut a = old.clone();
+        let perm = rng.shuffle_tracked(&mut a);
+        assert_ne!(a, old);
+        let b: Vec<u32> = perm.iter().map(|&i| old[i]).collect();
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn unshuffle_round_trip() {
+        let mut rng = Rng::from_seed(0);
+        for n in 0..20 {
+            let old: Vec<usize> = (0..n).collect();
+            let mut a = old.clone();
+            let log = rng.shuffle_recording(&mut a);
+            crate::unshuffle(&mut a, &log);
+            assert_eq!(a, old);
+        }
+        let mut a: Vec<u32> = (0..100).collect();
+        let mut b = a.clone();
+        Rng::from_seed(1).shuffle_recording(&mut a);
+        Rng::from_seed(1).shuffle(&mut b);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn range_f64() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..1000 {
+            let x = rng.rand_range_f64(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&x));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn loguniform() {
+        let mut rng = Rng::from_seed(0);
+        let (lo, hi) = (1E-5, 1E-1);
+        let mut hist = [0; 4];
+        for _ in 0..100000 {
+            let x = rng.rand_loguniform_f64(lo, hi);
+            assert!(lo <= x && x < hi);
+            let k = (f64::log10(x) + 5.0).floor() as usize;
+            hist[k.min(3)] += 1;
+        }
+        assert!(hist.iter().all(|&h| (h - 25000_i32).abs() < 1000));
+    }
+
+    #[test]
+    fn full_seed() {
+        let mut rng1 = Rng::from_full_seed((0xf4dbdf2183dcefb7, 0x1ad5be0d6dd28e9b));
+        let mut rng2 = Rng::from_seed(0);
+        for _ in 0..100 {
+            assert_eq!(rng1.rand_u64(), rng2.rand_u64());
+        }
+        let mut rng = Rng::from_full_seed((1, 2));
+        assert_eq!(rng.rand_u64(), 0x800045);
+    }
+
+    #[test]
+    #[should_panic]
+    fn full_seed_zero() {
+        Rng::from_full_seed((0, 0));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rand_bits() {
+        let mut rng = Rng::from_seed(0);
+        assert!(rng.rand_bitset(0).is_empty());
+        let a = rng.rand_bitset(100000 + 7);
+        assert_eq!(a.len(), 1563);
+        assert_eq!(a[1562] >> 39, 0);
+        let ones: u32 = a.iter().map(|x| x.count_ones()).sum();
+        assert!((ones as i32 - 50003).abs() < 1000);
+        let b = rng.rand_bits(10000);
+        assert_eq!(b.len(), 10000);
+        let ones = b.iter().filter(|&&x| x).count();
+        assert!((ones as i32 - 5000).abs() < 300);
+    }
+
+    #[test]
+    fn choice_weighted_u32() {
+        let mut rng = Rng::from_seed(0);
+        let items = ['a', 'b', 'c', 'd'];
+        let weights = [1, 0, 10, 89];
+        let mut hist = [0; 4];
+        for _ in 0..100000 {
+            let &c = rng.choice_weighted_u32(&items, &weights);
+            hist[(c as u8 - b'a') as usize] += 1;
+        }
+        assert_eq!(hist[1], 0);
+        assert!((hist[0] - 1000_i32).abs() < 150);
+        assert!((hist[2] - 10000_i32).abs() < 500);
+        assert!((hist[3] - 89000_i32).abs() < 1000);
+        assert_eq!(*rng.choice_weighted_u32(&items, &[0, 0, u32::MAX, 0]), 'c');
+    }
+
+    #[test]
+    fn rng_eq() {
+        let mut a = Rng::from_seed(0);
+        let mut b = a.clone();
+        assert!(a == b);
+        assert!(Rng::from_seed(0) != Rng::from_seed(1));
+        b.rand_u64();
+        assert!(a != b);
+        a.rand_u64();
+        assert!(a == b);
+    }
+
+    #[test]
+    fn fill_from_seed() {
+        for seed in 0..10 {
+            let mut a = [0; 37];
+            let mut b = [0; 37];
+            crate::fill_from_seed(seed, &mut a);
+            Rng::from_seed(seed).fill(&mut b);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn fill_simd_entropy() {
+        let mut rng = Rng::from_seed(0);
+        let mut a = vec![0; 1 << 20];
+        rng.fill_simd(&mut a);
+        let mut hist = [0; 256];
+        for &x in &a {hist[x as usize] += 1;}
+        let n = a.len() as f64;
+        let entropy: f64 = hist.iter().map(|&h| {
+            let p = h as f64/n;
+            -p*p.log2()
+        }).sum();
+        assert!(entropy > 7.999);
+
+        let mut b = [0; 45];
+        Rng::from_seed(0).fill_simd(&mut b);
+        assert_eq!(a[..45], b);
+        assert!(b[32..].iter().any(|&x| x != 0));
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn fill_simd_agrees_with_scalar_lanes() {
+        let s0 = [1, 2, 3, u64::MAX];
+        let s1 = [5, 0x8000_0000_0000_0001, 7, 9];
+        for &n in &[0, 1, 31, 32, 33, 100, 4096] {
+            let mut a = vec![0; n];
+            let mut b = vec![0; n];
+            crate::fill_lanes_scalar(s0, s1, &mut a);
+            #[cfg(target_arch = "x86_64")]
+            crate::fill_lanes_sse2(s0, s1, &mut b);
+            #[cfg(not(target_arch = "x86_64"))]
+            crate::fill_lanes_scalar(s0, s1, &mut b);
+            assert_eq!(a, b);
+        }
+        // The first lane is a plain Xorshift128+.
+        let mut a = [0; 64];
+        crate::fill_lanes_scalar(s0, s1, &mut a);
+        let mut lane = Rng::from_full_seed((s0[0], s1[0]));
+        assert_eq!(a[..8], lane.rand_u64().to_le_bytes());
+        assert_eq!(a[32..40], lane.rand_u64().to_le_bytes());
+    }
+
+    /// An adversarial generator, always returning the same value.
+    struct Constant(u32);
+
+    impl Rand for Constant {
+        fn from_seed(seed: u64) -> Self {Constant(seed as u32)}
+        fn rand_u32(&mut self) -> u32 {self.0}
+    }
+
+    #[test]
+    fn range_inclusive_f64() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..10000 {
+            let x = rng.rand_range_inclusive_f64(0.1, 0.3);
+            assert!((0.1..=0.3).contains(&x));
+        }
+        let mut max = Constant(u32::MAX);
+        assert_eq!(max.rand_f64_closed(), 1.0);
+        assert_eq!(max.rand_range_inclusive_f64(1.0, 6.0), 6.0);
+        assert_eq!(max.rand_range_inclusive_f64(0.1, 0.3), 0.3);
+        let mut min = Constant(0);
+        assert_eq!(min.rand_range_inclusive_f64(1.0, 6.0), 1.0);
+    }
+
+    #[cfg(feature = "std")]
+    fn mean_variance(a: impl Iterator<Item = f64>) -> (f64, f64) {
+        let mut stats = crate::Welford::new();
+        for x in a {stats.push(x);}
+        (stats.mean(), stats.variance())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn poisson_skellam() {
+        let mut rng = Rng::from_seed(0);
+        for &mu in &[0.5, 4.0, 25.0, 1000.0] {
+            let (mean, var) = mean_variance((0..100000)
+                .map(|_| rng.rand_poisson(mu) as f64));
+            assert!((mean/mu - 1.0).abs() < 0.02);
+            assert!((var/mu - 1.0).abs() < 0.05);
+        }
+        let (mu1, mu2) = (3.0, 12.0);
+        let (mean, var) = mean_variance((0..100000)
+            .map(|_| rng.rand_skellam(mu1, mu2) as f64));
+        assert!((mean - (mu1 - mu2)).abs() < 0.05);
+        assert!((var/(mu1 + mu2) - 1.0).abs() < 0.03);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "rand_poisson: mu must be finite and non-negative")]
+    fn poisson_infinite_mu() {
+        Rng::from_seed(0).rand_poisson(f64::INFINITY);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn welford() {
+        let a = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let n = a.len() as f64;
+        let mean = a.iter().sum::<f64>()/n;
+        let var = a.iter().map(|x| (x - mean)*(x - mean)).sum::<f64>()/(n - 1.0);
+        let mut stats = crate::Welford::new();
+        assert!(stats.mean().is_nan());
+        for &x in &a {stats.push(x);}
+        assert_eq!(stats.count(), 8);
+        assert!((stats.mean() - mean).abs() < 1E-12);
+        assert!((stats.variance() - var).abs() < 1E-12);
+        assert_eq!((stats.min(), stats.max()), (2.0, 9.0));
+    }
+
+    #[test]
+    fn index_weighted() {
+        let mut rng = Rng::from_seed(0);
+        let weights = [0.5, 0.0, 2.0, 7.5];
+        let mut hist = [0; 4];
+        for _ in 0..100000 {
+            hist[rng.rand_index_weighted(&weights)] += 1;
+        }
+        assert_eq!(hist[1], 0);
+        assert!((hist[0] - 5000_i32).abs() < 400);
+        assert!((hist[2] - 20000_i32).abs() < 800);
+        assert!((hist[3] - 75000_i32).abs() < 800);
+        assert_eq!(*rng.choice_weighted(&['a', 'b'], &[0.0, 1.0]), 'b');
+    }
+
+    #[test]
+    fn rand_f32_half_open() {
+        assert_eq!(Constant(u32::MAX).rand_f32(), 1.0 - 1.0/16777216.0);
+        assert_eq!(Constant(0).rand_f32(), 0.0);
+        for x in (u32::MAX - 1000)..=u32::MAX {
+            assert!(Constant(x).rand_f32() < 1.0);
+        }
+        let mut rng = Rng::from_seed(0);
+        assert!((0..10000).all(|_| (0.0..1.0).contains(&rng.rand_f32())));
+    }
+
+    #[test]
+    fn rand_f64_half_open() {
+        assert_eq!(Constant(u32::MAX).rand_f64(), 1.0 - 1.0/4294967296.0);
+        assert_eq!(Constant(0).rand_f64(), 0.0);
+        for x in (u32::MAX - 1000)..=u32::MAX {
+            assert!(Constant(x).rand_f64() < 1.0);
+        }
+        let mut rng = Rng::from_seed(0);
+        assert!((0..10000).all(|_| (0.0..1.0).contains(&rng.rand_f64())));
+    }
+
+    #[test]
+    fn choice_weighted_mut() {
+        let mut rng = Rng::from_seed(0);
+        let mut counts = [0; 3];
+        let weights = [1.0, 3.0, 6.0];
+        for _ in 0..100000 {
+            *rng.choice_weighted_mut(&mut counts, &weights) += 1;
+        }
+        assert!((counts[0] - 10000_i32).abs() < 600);
+        assert!((counts[1] - 30000_i32).abs() < 800);
+        assert!((counts[2] - 60000_i32).abs() < 800);
+    }
+
+    #[test]
+    fn shuffle_seeded() {
+        let mut a: Vec<u32> = (0..50).collect();
+        let mut b = a.clone();
+        let mut c = a.clone();
+        crate::shuffle_seeded(7, &mut a);
+        crate::shuffle_seeded(7, &mut b);
+        crate::shuffle_seeded(8, &mut c);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn engine_info() {
+        let info = Rng::engine_info();
+        assert_eq!(info.name, "Xorshift128+");
+        assert_eq!(info.period_bits, 128);
+        assert_eq!(info.state_bytes, 16);
+        assert_eq!(info.state_bytes, core::mem::size_of::<(u64, u64)>());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn simplex() {
+        let mut rng = Rng::from_seed(0);
+        let n = 5;
+        let mut mean = vec![0.0; n];
+        let count = 20000;
+        for _ in 0..count {
+            let x = rng.rand_simplex(n);
+            assert!((x.iter().sum::<f64>() - 1.0).abs() < 1E-12);
+            assert!(x.iter().all(|&xk| xk >= 0.0));
+            for (m, xk) in mean.iter_mut().zip(&x) {*m += xk/count as f64;}
+        }
+        assert!(mean.iter().all(|m| (m - 0.2).abs() < 0.01));
+        assert_eq!(rng.rand_simplex(1), [1.0]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn utf8_string() {
+        let mut rng = Rng::from_seed(0);
+        for max_bytes in 0..200 {
+            let s = rng.rand_utf8_string(max_bytes);
+            assert!(s.len() <= max_bytes);
+            assert!(std::str::from_utf8(s.as_bytes()).is_ok());
+        }
+        let mut lens = [false; 5];
+        for _ in 0..100000 {
+            lens[rng.rand_char().len_utf8()] = true;
+        }
+        assert_eq!(lens, [false, true, true, true, true]);
+    }
+
+    /// An adversarial generator, returning zero first.
+    struct ZeroFirst(u32);
+
+    impl Rand for ZeroFirst {
+        fn from_seed(_: u64) -> Self {ZeroFirst(0)}
+        fn rand_u32(&mut self) -> u32 {
+            self.0 += 1;
+            if self.0 <= 2 {0} else {self.0}
+        }
+    }
+
+    #[test]
+    fn nonzero() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..1000 {
+            assert!(rng.rand_nonzero_u32().get() != 0);
+            assert!(rng.rand_nonzero_u64().get() != 0);
+        }
+        assert_eq!(ZeroFirst::from_seed(0).rand_nonzero_u32().get(), 3);
+        assert_eq!(ZeroFirst::from_seed(0).rand_nonzero_u64().get(), 3 << 32 | 4);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn fenwick_sampler() {
+        use crate::FenwickSampler;
+        let mut rng = Rng::from_seed(0);
+        let mut sampler = FenwickSampler::new(&[1.0, 1.0, 1.0, 1.0, 1.0]);
+        sampler.update(0, 0.0);
+        sampler.update(1, 4.0);
+        sampler.update(3, 0.0);
+        sampler.update(4, 5.0);
+        sampler.update(4, 3.0);
+        assert_eq!(sampler.total(), 8.0);
+        let mut hist = [0; 5];
+        for _ in 0..80000 {
+            hist[sampler.sample(&mut rng)] += 1;
+        }
+        assert_eq!((hist[0], hist[3]), (0, 0));
+        assert!((hist[1] - 40000_i32).abs() < 800);
+        assert!((hist[2] - 10000_i32).abs() < 500);
+        assert!((hist[4] - 30000_i32).abs() < 800);
+
+        for n in 1..20 {
+            let mut sampler = FenwickSampler::new(&vec![0.0; n]);
+            sampler.update(n - 1, 1.0);
+            assert_eq!(sampler.sample(&mut rng), n - 1);
+            sampler.update(n - 1, 0.0);
+            sampler.update(0, 2.0);
+            assert_eq!(sampler.sample(&mut rng), 0);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rand_obj() {
+        use crate::RandObj;
+        let mut engines: Vec<Box<dyn RandObj>> = vec![
+            Box::new(Rng::from_seed(0)),
+            Box::new(Constant(7))
+        ];
+        let mut rng = Rng::from_seed(0);
+        assert_eq!(engines[0].next_u64(), rng.rand_u64());
+        assert_eq!(engines[0].rand_range_u32(10, 20), rng.rand_range_u32(10, 20));
+        assert_eq!(engines[1].next_u32(), 7);
+        let mut a = [0; 4];
+        engines[1].fill_bytes(&mut a);
+        assert_eq!(a, [7, 0, 0, 0]);
+        assert_eq!(engines[1].rand_u64(), 7 << 32 | 7);
+        let mut boxed = <Box<dyn RandObj>>::from_seed(1);
+        assert_eq!(boxed.rand_u64(), Rng::from_seed(1).rand_u64());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn multivariate_normal() {
+        let mut rng = Rng::from_seed(0);
+        let mean = [1.0, -2.0];
+        // Covariance [[4.0, 1.2], [1.2, 1.0]].
+        let l = [2.0, 99.0, 0.6, 0.8];
+        let count = 100000;
+        let mut samples = vec![[0.0; 2]; count];
+        for x in &mut samples {
+            rng.rand_multivariate_normal(&mean, &l, 2, x);
+        }
+        let mut m = [0.0; 2];
+        for x in &samples {
+            m[0] += x[0]/count as f64;
+            m[1] += x[1]/count as f64;
+        }
+        let mut cov = [0.0; 4];
+        for x in &samples {
+            for i in 0..2 {
+                for j in 0..2 {
+                    cov[2*i + j] += (x[i] - m[i])*(x[j] - m[j])/count as f64;
+                }
+            }
+        }
+        assert!((m[0] - 1.0).abs() < 0.03 && (m[1] + 2.0).abs() < 0.02);
+        let target = [4.0, 1.2, 1.2, 1.0];
+        assert!(cov.iter().zip(&target).all(|(c, t)| (c - t).abs() < 0.05));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn shuffle_deque() {
+        use std::collections::VecDeque;
+        let mut rng = Rng::from_seed(0);
+        let mut d: VecDeque<u32> = (0..10).collect();
+        d.rotate_left(3);
+        let mut orders = std::collections::HashSet::new();
+        for _ in 0..100 {
+            rng.shuffle_deque(&mut d);
+            let mut a: Vec<u32> = d.iter().copied().collect();
+            orders.insert(a.clone());
+            a.sort();
+            assert_eq!(a, (0..10).collect::<Vec<u32>>());
+        }
+        assert!(orders.len() > 90);
+        let mut empty: VecDeque<u32> = VecDeque::new();
+        rng.shuffle_deque(&mut empty);
+    }
+
+    #[test]
+    fn range_f64_invalid() {
+        let cases = [
+            (f64::NAN, 1.0), (0.0, f64::NAN), (f64::NEG_INFINITY, 0.0),
+            (0.0, f64::INFINITY), (1.0, 1.0), (2.0, 1.0), (-f64::MAX, f64::MAX)
+        ];
+        for &(a, b) in &cases {
+            let result = std::panic::catch_unwind(|| {
+                Rng::from_seed(0).rand_range_f64(a, b)
+            });
+            assert!(result.is_err(), "accepted {}..{}", a, b);
+            let result = std::panic::catch_unwind(|| {
+                Rng::from_seed(0).rand_range_inclusive_f64(a, b)
+            });
+            assert_eq!(result.is_err(), a != b, "{}..={}", a, b);
+        }
+        assert_eq!(Rng::from_seed(0).rand_range_inclusive_f64(1.0, 1.0), 1.0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn shuffle_buffer() {
+        let mut rng = Rng::from_seed(0);
+        for &capacity in &[0, 1, 10, 100] {
+            let mut buffer = crate::ShuffleBuffer::new(capacity);
+            let mut out = Vec::new();
+            for x in 0..10000 {
+                out.extend(buffer.push(x, &mut rng));
+            }
+            out.extend(buffer.drain(&mut rng));
+            if capacity > 1 {
+                assert!(out.iter().enumerate().any(|(i, &x)| i != x));
+            }
+            out.sort();
+            assert_eq!(out, (0..10000).collect::<Vec<_>>());
+        }
+    }
+
+    fn is_even_permutation(p: &[usize]) -> bool {
+        let mut visited = vec![false; p.len()];
+        let mut transpositions = 0;
+        for i in 0..p.len() {
+            let mut j = i;
+            let mut len = 0;
+            while !visited[j] {
+                visited[j] = true;
+                j = p[j];
+                len += 1;
+            }
+            if len > 0 {transpositions += len - 1;}
+        }
+        transpositions % 2 == 0
+    }
+
+    #[test]
+    fn shuffle_with_parity() {
+        let mut rng = Rng::from_seed(0);
+        let mut hist = std::collections::HashMap::new();
+        for k in 0..24000 {
+            let even = k % 2 == 0;
+            let mut a = [0, 1, 2, 3];
+            rng.shuffle_with_parity(&mut a, even);
+            assert_eq!(is_even_permutation(&a), even);
+            *hist.entry(a).or_insert(0) += 1;
+        }
+        assert_eq!(hist.len(), 24);
+        assert!(hist.values().all(|&h| (h - 1000_i32).abs() < 150));
+        let mut a = [0];
+        rng.shuffle_with_parity(&mut a, true);
+        let mut a = [0, 1];
+        rng.shuffle_with_parity(&mut a, false);
+        assert_eq!(a, [1, 0]);
+    }
+
+    #[test]
+    fn splitmix64() {
+        // Reference values of SplitMix64 seeded with 0.
+        assert_eq!(crate::splitmix64(0), 0xe220a8397b1dcdaf);
+        assert_eq!(crate::splitmix64(0x9e3779b97f4a7c15), 0x6e789e6aa1b965f4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_time_diverges() {
+        let mut a = Rng::from_time();
+        let mut b = Rng::from_time();
+        assert_ne!(a.rand_u64(), b.rand_u64());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sample_fraction() {
+        let mut rng = Rng::from_seed(0);
+        let a: Vec<u32> = (0..100000).collect();
+        let s = rng.sample_fraction(&a, 0.3);
+        assert!((s.len() as i32 - 30000).abs() < 600);
+        assert!(s.windows(2).all(|w| w[0] < w[1]));
+        assert!(rng.sample_fraction(&a, 0.0).is_empty());
+        assert_eq!(rng.sample_fraction(&a, 1.0).len(), a.len());
+    }
+
+    #[test]
+    fn split_shuffle() {
+        let mut rng = Rng::from_seed(0);
+        let mut a: Vec<u32> = (0..100).collect();
+        let (left, right) = rng.split_shuffle(&mut a, 30);
+        assert_eq!((left.len(), right.len()), (30, 70));
+        let mut all: Vec<u32> = left.iter().chain(right.iter()).copied().collect();
+        assert!(all.iter().enumerate().any(|(i, &x)| i as u32 != x));
+        all.sort();
+        assert_eq!(all, (0..100).collect::<Vec<u32>>());
+        let (left, right) = rng.split_shuffle(&mut a, 100);
+        assert_eq!((left.len(), right.len()), (100, 0));
+    }
+
+    #[test]
+    fn digit_radix() {
+        let mut rng = Rng::from_seed(0);
+        for radix in 2..=36 {
+            let mut hist = [0; 36];
+            for _ in 0..radix*1000 {
+                let c = rng.rand_digit_radix(radix);
+                hist[c.to_digit(radix).unwrap() as usize] += 1;
+            }
+            assert!(hist[..radix as usize].iter().all(|&h| (h - 1000_i32).abs() < 150));
+        }
+        let result = std::panic::catch_unwind(|| Rng::from_seed(0).rand_digit_radix(37));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn shuffle_portable() {
+        let mut a: Vec<u32> = (0..10).collect();
+        Rng::from_seed(0).shuffle_portable(&mut a);
+        assert_eq!(a, [5, 7, 1, 3, 0, 9, 2, 8, 6, 4]);
+        #[cfg(target_pointer_width = "64")] {
+            let mut b: Vec<u32> = (0..10).collect();
+            Rng::from_seed(0).shuffle(&mut b);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn peek_u64() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..100 {
+            let x = rng.peek_u64();
+            assert_eq!(rng.peek_u64(), x);
+            assert_eq!(rng.rand_u64(), x);
+        }
+    }
+
+    #[test]
+    fn range_isize() {
+        let mut rng = Rng::from_seed(0);
+        for &(a, b) in &[(-10, 10), (isize::MIN, isize::MAX), (isize::MIN, 1), (-1, isize::MAX)] {
+            for _ in 0..1000 {
+                let x = rng.rand_range_isize(a, b);
+                assert!(a <= x && x < b);
+            }
+        }
+        let mut rng1 = Rng::from_seed(0);
+        let mut rng2 = Rng::from_seed(0);
+        for _ in 0..100 {
+            #[cfg(target_pointer_width = "64")]
+            assert_eq!(rng1.rand_range_isize(-5, 5) as i64, rng2.rand_range_i64(-5, 5));
+            #[cfg(target_pointer_width = "32")]
+            assert_eq!(rng1.rand_range_isize(-5, 5) as i32, rng2.rand_range_i32(-5, 5));
+        }
+    }
+
+    #[test]
+    fn no_zero_state() {
+        assert!(!crate::validate_state((0, 0)));
+        // from_seed cannot produce (0, 0), because the two XOR constants
+        // differ. The seeds zeroing one component leave the other.
+        for &seed in &[0, 1, u64::MAX, 0xf4dbdf2183dcefb7, 0x1ad5be0d6dd28e9b] {
+            assert!(crate::validate_state(Rng::from_seed(seed).state));
+        }
+        // from_full_seed rejects it.
+        assert!(std::panic::catch_unwind(|| Rng::from_full_seed((0, 0))).is_err());
+        assert_eq!(Rng::from_full_seed((0, 1)).state, (0, 1));
+        #[cfg(feature = "std")]
+        assert!(crate::validate_state(Rng::from_time().state));
+    }
+
+    #[test]
+    fn enumerated_iter() {
+        let mut rng = Rng::from_seed(0);
+        let a: Vec<usize> = rng.enumerated_iter(|_, i| i).take(10).collect();
+        assert_eq!(a, (0..10).collect::<Vec<usize>>());
+        let b: Vec<u64> = rng.enumerated_iter(|rng, i| rng.rand_u64() ^ i as u64)
+            .take(10).collect();
+        let mut rng = Rng::from_seed(0);
+        assert!(b.iter().enumerate().all(|(i, &x)| x == rng.rand_u64() ^ i as u64));
+    }
+
+    #[cfg(feature = "std")]
+    fn is_rotation(m: &[f64], n: usize) -> bool {
+        // Orthonormal: M M^T = I.
+        for i in 0..n {
+            for j in 0..n {
+                let dot: f64 = (0..n).map(|k| m[i*n + k]*m[j*n + k]).sum();
+                let expected = if i == j {1.0} else {0.0};
+                if (dot - expected).abs() > 1E-12 {return false;}
+            }
+        }
+        let det = if n == 2 {
+            m[0]*m[3] - m[1]*m[2]
+        } else {
+            m[0]*(m[4]*m[8] - m[5]*m[7]) - m[1]*(m[3]*m[8] - m[5]*m[6])
+                + m[2]*(m[3]*m[7] - m[4]*m[6])
+        };
+        (det - 1.0).abs() < 1E-12
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rotations() {
+        let mut rng = Rng::from_seed(0);
+        let mut mean = [0.0; 9];
+        for _ in 0..10000 {
+            assert!(is_rotation(&rng.rand_rotation_2d(), 2));
+            let m = rng.rand_rotation_3d();
+            assert!(is_rotation(&m, 3));
+            for (mk, x) in mean.iter_mut().zip(&m) {*mk += x/10000.0;}
+        }
+        // Uniformity implies that all entries have mean zero.
+        assert!(mean.iter().all(|m| m.abs() < 0.03));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn quaternion() {
+        use core::f64::consts::PI;
+        let mut rng = Rng::from_seed(0);
+        let n = 100000;
+        let mut hist = [0; 4];
+        for _ in 0..n {
+            let q = rng.rand_quaternion();
+            assert!((q.iter().map(|x| x*x).sum::<f64>() - 1.0).abs() < 1E-12);
+            let angle = 2.0*q[0].abs().min(1.0).acos();
+            hist[((angle/PI*4.0) as usize).min(3)] += 1;
+        }
+        // The Haar measure has the angle distribution (t - sin t)/pi.
+        let cdf = |t: f64| (t - t.sin())/PI;
+        for (k, &h) in hist.iter().enumerate() {
+            let (a, b) = (k as f64*PI/4.0, (k + 1) as f64*PI/4.0);
+            let expected = (cdf(b) - cdf(a))*n as f64;
+            assert!((h as f64 - expected).abs() < 0.02*n as f64);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn choice_excluding() {
+        let mut rng = Rng::from_seed(0);
+        for excluded in [vec![1, 3], vec![0, 1, 2, 4, 5, 7, 9, 9, 20]] {
+            let mut hist = [0; 10];
+            for _ in 0..10000 {
+                hist[rng.choice_excluding(10, &excluded).unwrap()] += 1;
+            }
+            let allowed = (0..10).filter(|i| !excluded.contains(i)).count() as i32;
+            for (i, &h) in hist.iter().enumerate() {
+                if excluded.contains(&i) {
+                    assert_eq!(h, 0);
+                } else {
+                    assert!((h - 10000/allowed).abs() < 300);
+                }
+            }
+        }
+        assert_eq!(rng.choice_excluding(3, &[2, 0, 1]), None);
+        assert_eq!(rng.choice_excluding(0, &[]), None);
+        assert_eq!(rng.choice_excluding(3, &[2, 0]), Some(1));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn gbm_path() {
+        let mut rng = Rng::from_seed(0);
+        let (s0, mu, sigma, dt, steps) = (100.0, 0.05, 0.2, 1.0/12.0, 24);
+        let t = dt*steps as f64;
+        let (mean, var) = mean_variance((0..20000).map(|_| {
+            let path = rng.gbm_path(s0, mu, sigma, dt, steps);
+            assert_eq!(path.len(), steps + 1);
+            assert_eq!(path[0], s0);
+            f64::ln(path[steps]/s0)
+        }));
+        assert!((mean - (mu - 0.5*sigma*sigma)*t).abs() < 0.01);
+        assert!((var - sigma*sigma*t).abs() < 0.005);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn draw_indices() {
+        let mut rng = Rng::from_seed(0);
+        for n in 0..50 {
+            let mut a: Vec<usize> = rng.draw_indices(n).collect();
+            assert_eq!(a.len(), n);
+            a.sort();
+            assert_eq!(a, (0..n).collect::<Vec<usize>>());
+        }
+        let mut hist = [0; 10];
+        for _ in 0..10000 {
+            hist[rng.draw_indices(10).next().unwrap()] += 1;
+        }
+        assert!(hist.iter().all(|&h| (h - 1000_i32).abs() < 150));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn alloc_apis() {
+        // All of these are available without std, given an allocator.
+        // Tests are built with std, the actual no_std build is checked
+        // by the crate in ci/no_std_check.
+        let mut rng = Rng::from_seed(0);
+        let mut a = [1, 2, 3, 4];
+        let mut d: alloc::collections::VecDeque<u32> = (0..4).collect();
+        assert_eq!(rng.rand_bytes(3).len(), 3);
+        assert_eq!(rng.rand_bits(3).len(), 3);
+        assert_eq!(rng.rand_bitset(65).len(), 2);
+        assert!(rng.rand_utf8_string(8).len() <= 8);
+        assert_eq!(rng.shuffle_tracked(&mut a).len(), 4);
+        let log = rng.shuffle_recording(&mut a);
+        crate::unshuffle(&mut a, &log);
+        rng.shuffle_deque(&mut d);
+        assert!(rng.choice_excluding(4, &[0]).is_some());
+        assert!(rng.sample_fraction(&a, 0.5).len() <= 4);
+        assert_eq!(rng.draw_indices(4).count(), 4);
+        assert!(crate::FenwickSampler::new(&[1.0]).sample(&mut rng) == 0);
+        assert_eq!(crate::ShuffleBuffer::new(2).push(1, &mut rng), None);
+        let mut boxed: alloc::boxed::Box<dyn crate::RandObj> = alloc::boxed::Box::new(rng);
+        boxed.rand_u32();
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn rand_bounded_usize_wide() {
+        let mut rng = Rng::from_seed(0);
+        let m = 3 * (u32::MAX as usize) + 7;
+        let mut above = 0;
+        for _ in 0..1000 {
+            let x = rng.rand_bounded_usize(m);
+            assert!(x < m);
+            if x > u32::MAX as usize {above += 1;}
+        }
+        // About two thirds of the samples exceed u32::MAX.
+        assert!(above > 600 && above < 730);
+    }
+
+    #[test]
+    fn rand_u64_scrambled() {
+        let mut rng = Rng::from_seed(0);
+        let mut hist = [0u32; 256];
+        for _ in 0..256000 {
+            hist[(rng.rand_u64_scrambled() & 0xff) as usize] += 1;
+        }
+        // Chi-square with 255 degrees of freedom, p < 0.001 above 330.5.
+        let chi2: f64 = hist.iter()
+            .map(|&h| {let d = h as f64 - 1000.0; d*d/1000.0}).sum();
+        assert!(chi2 < 330.5, "chi2 = {}", chi2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn erdos_renyi() {
+        let mut rng = Rng::from_seed(0);
+        assert!(rng.erdos_renyi(10, 0.0).is_empty());
+        assert_eq!(rng.erdos_renyi(10, 1.0).len(), 45);
+        assert!(rng.erdos_renyi(0, 0.5).is_empty());
+        assert!(rng.erdos_renyi(1, 0.5).is_empty());
+        for &p in &[0.001, 0.1, 0.5, 0.9] {
+            let n = 300;
+            let pairs = (n*(n - 1)/2) as f64;
+            let edges = rng.erdos_renyi(n, p);
+            assert!(edges.iter().all(|&(i, j)| i < j && j < n));
+            assert!(edges.windows(2).all(|w| (w[0].1, w[0].0) < (w[1].1, w[1].0)));
+            let expected = p*pairs;
+            let sd = (pairs*p*(1.0 - p)).sqrt();
+            assert!((edges.len() as f64 - expected).abs() < 5.0*sd,
+                "p = {}: {} edges", p, edges.len());
+        }
+        // Tiny p, for which 1.0 - p rounds to 1.0.
+        assert!(rng.erdos_renyi(100, 1E-17).is_empty());
+        assert!(rng.erdos_renyi(100, 1E-300).is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic]
+    fn erdos_renyi_invalid_p() {
+        Rng::from_seed(0).erdos_renyi(4, 1.5);
+    }
+
+    #[test]
+    fn choice_range() {
+        let mut rng = Rng::from_seed(0);
+        assert_eq!(rng.choice_range(0..0), None);
+        assert_eq!(rng.choice_range(core::ops::Range {start: 5, end: 3}), None);
+        assert_eq!(rng.choice_range(7..8), Some(7));
+        let mut hist = [0; 4];
+        for _ in 0..4000 {
+            let x = rng.choice_range(10..14).unwrap();
+            hist[x - 10] += 1;
+        }
+        assert!(hist.iter().all(|&h| (h - 1000_i32).abs() < 150));
+        let big = rng.choice_range(usize::MAX - 2..usize::MAX).unwrap();
+        assert!(big >= usize::MAX - 2);
+    }
+
+    #[test]
+    fn rand_sign() {
+        let mut rng = Rng::from_seed(0);
+        let mut sum = 0;
+        for _ in 0..10000 {
+            let s = rng.rand_sign();
+            assert!(s == 1 || s == -1);
+            sum += s;
+        }
+        assert!(sum.abs() < 400);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn random_walk_1d() {
+        let mut rng = Rng::from_seed(0);
+        let steps = 100;
+        let (mean, var) = mean_variance((0..20000).map(|_| {
+            let path = rng.random_walk_1d(steps);
+            assert_eq!(path.len(), steps + 1);
+            assert_eq!(path[0], 0);
+            assert!(path.windows(2).all(|w| (w[1] - w[0]).abs() == 1));
+            path[steps] as f64
+        }));
+        assert!(mean.abs() < 0.3);
+        assert!((var - steps as f64).abs() < 5.0);
+    }
+
+    #[test]
+    fn rand_bounded_u32_counted() {
+        let mut rng = Rng::from_seed(0);
+        for k in 0..32 {
+            assert_eq!(rng.rand_bounded_u32_counted(1 << k).1, 1);
+        }
+        let mut a = rng.clone();
+        let mut b = rng.clone();
+        let mut total = 0;
+        let mut max = 0;
+        for _ in 0..1000 {
+            let (x, count) = a.rand_bounded_u32_counted(0x8000_0001);
+            assert_eq!(x, b.rand_bounded_u32(0x8000_0001));
+            let mut c = rng.clone();
+            for _ in 0..count {c.rand_u32();}
+            rng = a.clone();
+            assert!(c == rng);
+            total += count;
+            max = max.max(count);
+        }
+        // About two draws per sample are expected.
+        assert!(max > 1);
+        assert!((1800..2200).contains(&total));
+    }
+
+    #[test]
+    fn mix_seed() {
+        const SEED: u64 = crate::mix_seed(1, 2);
+        let (a, b) = (std::hint::black_box(1), std::hint::black_box(2));
+        assert_eq!(SEED, crate::mix_seed(a, b));
+        assert_eq!(SEED, crate::splitmix64(1 ^ crate::splitmix64(2)));
+        assert_ne!(SEED, crate::mix_seed(2, 1));
+    }
+
+    #[test]
+    fn shuffle_chi_square() {
+        // All 24 permutations of four elements must be equally likely.
+        let mut rng = Rng::from_seed(0);
+        let n = 96000;
+        let mut hist = [0u32; 256];
+        for _ in 0..n {
+            let mut a = [0, 1, 2, 3];
+            rng.shuffle(&mut a);
+            hist[a[0]*64 + a[1]*16 + a[2]*4 + a[3]] += 1;
+        }
+        let counts: Vec<u32> = hist.iter().copied().filter(|&h| h > 0).collect();
+        assert_eq!(counts.len(), 24);
+        let expected = n as f64/24.0;
+        let chi2: f64 = counts.iter()
+            .map(|&h| {let d = h as f64 - expected; d*d/expected}).sum();
+        // Chi-square with 23 degrees of freedom, p < 0.001 above 49.73.
+        assert!(chi2 < 49.73, "chi2 = {}", chi2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sample_logits() {
+        let mut rng = Rng::from_seed(0);
+        let logits = [1.0, 2.0, 0.0, f64::NEG_INFINITY, 3.0];
+        let z: f64 = logits.iter().map(|&x| f64::exp(x)).sum();
+        let n = 100000;
+        let mut hist = [0; 5];
+        for _ in 0..n {hist[rng.sample_logits(&logits)] += 1;}
+        assert_eq!(hist[3], 0);
+        for (&h, &x) in hist.iter().zip(&logits) {
+            let expected = n as f64*f64::exp(x)/z;
+            assert!((h as f64 - expected).abs() < 0.03*n as f64);
+        }
+        // Large logits do not overflow.
+        let logits = [1000.0, 1000.0 + f64::ln(3.0)];
+        let mut hist = [0; 2];
+        for _ in 0..10000 {hist[rng.sample_logits(&logits)] += 1;}
+        assert!((hist[1] - 7500_i32).abs() < 300);
+        assert_eq!(rng.sample_logits(&[-5.0]), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "sample_logits_temperature: all logits are negative infinity")]
+    fn sample_logits_all_neg_inf() {
+        Rng::from_seed(0).sample_logits(&[f64::NEG_INFINITY; 3]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "sample_logits_temperature: all logits are negative infinity")]
+    fn sample_logits_temperature_zero_all_neg_inf() {
+        Rng::from_seed(0).sample_logits_temperature(&[f64::NEG_INFINITY; 3], 0.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sample_logits_temperature() {
+        let mut rng = Rng::from_seed(0);
+        let logits = [1.0, 2.5, 2.0, 0.0];
+        let state = rng.clone();
+        assert_eq!(rng.sample_logits_temperature(&logits, 0.0), 1);
+        assert!(rng == state);
+        assert_eq!(rng.sample_logits_temperature(&[2.0, 2.0], 0.0), 0);
+        let mut a = rng.clone();
+        assert_eq!(rng.sample_logits(&logits), a.sample_logits_temperature(&logits, 1.0));
+        let mut hist = [0; 4];
+        for _ in 0..10000 {hist[rng.sample_logits_temperature(&logits, 0.05)] += 1;}
+        assert!(hist[1] > 9900);
+        let mut hist = [0; 4];
+        for _ in 0..10000 {hist[rng.sample_logits_temperature(&logits, 1000.0)] += 1;}
+        assert!(hist.iter().all(|&h| (h - 2500_i32).abs() < 200));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sample_top_k_top_p() {
+        let mut rng = Rng::from_seed(0);
+        let logits = [0.5, 3.0, 1.0, 2.0, 0.0];
+        let mut hist = [0; 5];
+        for _ in 0..10000 {hist[rng.sample_top_k(&logits, 2)] += 1;}
+        assert_eq!((hist[0], hist[2], hist[4]), (0, 0, 0));
+        // Renormalized: 1/(1 + e^-1) for the largest logit.
+        assert!((hist[1] - 7311_i32).abs() < 300);
+        assert_eq!(rng.sample_top_k(&logits, 1), 1);
+        let mut hist = [0; 5];
+        for _ in 0..1000 {hist[rng.sample_top_k(&logits, 10)] += 1;}
+        assert!(hist.iter().all(|&h| h > 0));
+
+        // Softmax: 0.0675, 0.6037, 0.0821, 0.1793, 0.0450.
+        let mut hist = [0; 5];
+        for _ in 0..10000 {hist[rng.sample_top_p(&logits, 0.7)] += 1;}
+        assert_eq!((hist[0], hist[2], hist[4]), (0, 0, 0));
+        assert!(hist[1] > 0 && hist[3] > 0);
+        let mut hist = [0; 5];
+        for _ in 0..1000 {hist[rng.sample_top_p(&logits, 0.5)] += 1;}
+        assert_eq!(hist[1], 1000);
+        let mut hist = [0; 5];
+        for _ in 0..1000 {hist[rng.sample_top_p(&logits, 1.0)] += 1;}
+        assert!(hist.iter().all(|&h| h > 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "sample_top_k: all logits are negative infinity")]
+    fn sample_top_k_all_neg_inf() {
+        Rng::from_seed(0).sample_top_k(&[f64::NEG_INFINITY; 3], 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "sample_top_p: largest logit is not finite")]
+    fn sample_top_p_all_neg_inf() {
+        Rng::from_seed(0).sample_top_p(&[f64::NEG_INFINITY; 3], 0.9);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rand_in_annulus() {
+        let mut rng = Rng::from_seed(0);
+        let (r1, r2) = (1.0, 3.0);
+        let mut hist = [0; 8];
+        for _ in 0..16000 {
+            let (x, y) = rng.rand_in_annulus(r1, r2);
+            let r = f64::hypot(x, y);
+            assert!(r >= r1 - 1E-12 && r <= r2 + 1E-12);
+            let k = ((r*r - r1*r1)/(r2*r2 - r1*r1)*8.0) as usize;
+            hist[k.min(7)] += 1;
+        }
+        assert!(hist.iter().all(|&h| (h - 2000_i32).abs() < 200));
+        let (x, y) = rng.rand_in_annulus(2.0, 2.0);
+        assert!((f64::hypot(x, y) - 2.0).abs() < 1E-12);
+        assert_eq!(rng.rand_in_annulus(0.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn rand_in_box() {
+        let mut rng = Rng::from_seed(0);
+        let (min, max) = ([-1.0, 0.0, 5.0], [1.0, 0.0, 7.5]);
+        let mut p = [0.0; 3];
+        for _ in 0..1000 {
+            rng.rand_in_box(&min, &max, &mut p);
+            assert!((0..3).all(|i| min[i] <= p[i] && p[i] <= max[i]));
+        }
+        rng.rand_in_box(&[], &[], &mut []);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rand_in_triangle() {
+        let mut rng = Rng::from_seed(0);
+        let (a, b, c) = ((0.0, 0.0), (4.0, 1.0), (1.0, 3.0));
+        let cross = |p: (f64, f64), q: (f64, f64), r: (f64, f64)|
+            (q.0 - p.0)*(r.1 - p.1) - (q.1 - p.1)*(r.0 - p.0);
+        // The triangle is split into four congruent ones by the midpoints.
+        let mid = |p: (f64, f64), q: (f64, f64)| ((p.0 + q.0)/2.0, (p.1 + q.1)/2.0);
+        let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+        let mut inner = 0;
+        for _ in 0..10000 {
+            let p = rng.rand_in_triangle(a, b, c);
+            assert!(cross(a, b, p) >= -1E-12 && cross(b, c, p) >= -1E-12
+                && cross(c, a, p) >= -1E-12);
+            if cross(ab, bc, p) > 0.0 && cross(bc, ca, p) > 0.0
+                && cross(ca, ab, p) > 0.0 {inner += 1;}
+        }
+        assert!((inner - 2500_i32).abs() < 200);
+    }
+
+    #[test]
+    fn shuffle_keyed() {
+        let shuffled = |key: &str| {
+            let mut a: Vec<u32> = (0..20).collect();
+            crate::shuffle_keyed(key, &mut a);
+            a
+        };
+        assert_eq!(shuffled("users"), shuffled("users"));
+        assert_ne!(shuffled("users"), shuffled("orders"));
+        let mut a = shuffled("users");
+        a.sort();
+        assert_eq!(a, (0..20).collect::<Vec<u32>>());
+        let (mut a, mut b) = ([1, 2, 3, 4, 5, 6], [1, 2, 3, 4, 5, 6]);
+        crate::shuffle_keyed(&(7_usize, "x"), &mut a);
+        crate::shuffle_keyed(&(7_usize, "x"), &mut b);
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn rand_composition() {
+        let mut rng = Rng::from_seed(0);
+        assert!(rng.rand_composition(0, 0).is_empty());
+        assert_eq!(rng.rand_composition(7, 1), vec![7]);
+        assert_eq!(rng.rand_composition(0, 3), vec![0, 0, 0]);
+        for _ in 0..1000 {
+            let a = rng.rand_composition(20, 5);
+            assert_eq!(a.len(), 5);
+            assert_eq!(a.iter().sum::<u64>(), 20);
+        }
+        let a = rng.rand_composition(u64::MAX - 3, 4);
+        assert_eq!(a.iter().fold(0_u64, |s, &x| s.checked_add(x).unwrap()), u64::MAX - 3);
+        // All six compositions of 2 into 3 parts are equally likely.
+        let mut hist = std::collections::HashMap::new();
+        for _ in 0..6000 {
+            *hist.entry(rng.rand_composition(2, 3)).or_insert(0) += 1;
+        }
+        assert_eq!(hist.len(), 6);
+        assert!(hist.values().all(|&h| (h - 1000_i32).abs() < 150));
+    }
+
+    #[test]
+    fn next_u64_raw() {
+        let mut a = Rng::from_seed(0);
+        let mut b = Rng::from_seed(0);
+        for _ in 0..1000 {
+            assert_eq!(a.next_u64_raw(), b.rand_u64());
+        }
+        assert!(a == b);
+    }
+
+    #[test]
+    fn rand_bounded_u32_capped() {
+        assert_eq!(Constant(u32::MAX).rand_bounded_u32_capped(3, 100), None);
+        assert_eq!(Constant(u32::MAX).rand_bounded_u32_capped(0, 100), None);
+        assert_eq!(Constant(u32::MAX).rand_bounded_u32_capped(4, 1), Some(3));
+        assert_eq!(Constant(2).rand_bounded_u32_capped(3, 1), Some(2));
+        let mut rng = Rng::from_seed(0);
+        assert_eq!(rng.rand_bounded_u32_capped(10, 0), None);
+        let mut a = rng.clone();
+        for _ in 0..1000 {
+            assert_eq!(rng.rand_bounded_u32_capped(1000, 64), Some(a.rand_bounded_u32(1000)));
+        }
+    }
+
+    #[test]
+    fn rand_range_inclusive_int() {
+        let mut rng = Rng::from_seed(0);
+        let mut hist = [0; 3];
+        for _ in 0..3000 {
+            hist[rng.rand_range_inclusive_u32(4, 6) as usize - 4] += 1;
+            let x = rng.rand_range_inclusive_i32(-1, 1);
+            assert!((-1..=1).contains(&x));
+            let x = rng.rand_range_inclusive_i64(i64::MIN, i64::MIN + 1);
+            assert!(x <= i64::MIN + 1);
+        }
+        assert!(hist.iter().all(|&h| (h - 1000_i32).abs() < 150));
+        assert_eq!(rng.rand_range_inclusive_u64(7, 7), 7);
+        assert_eq!(rng.rand_range_inclusive_i32(-7, -7), -7);
+        rng.rand_range_inclusive_u32(0, u32::MAX);
+        rng.rand_range_inclusive_i64(i64::MIN, i64::MAX);
+        let mut a = Rng::from_seed(1);
+        let mut b = Rng::from_seed(1);
+        assert_eq!(a.rand_range_inclusive_u64(0, u64::MAX), b.rand_u64());
+    }
+
+    #[test]
+    fn rand_macro() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..1000 {
+            let x = crate::rand!(rng, 1..=6);
+            assert!((1..=6).contains(&x));
+            let x: u32 = crate::rand!(rng, 10..20);
+            assert!((10..20).contains(&x));
+            let x: u64 = crate::rand!(rng, 0..=1 << 40);
+            assert!(x <= 1 << 40);
+            let x: i64 = crate::rand!(rng, -5..5);
+            assert!((-5..5).contains(&x));
+            let x = crate::rand!(rng, 3..=4_usize);
+            assert!((3..=4).contains(&x));
+            let x = crate::rand!(rng, 0..7_usize);
+            assert!(x < 7);
+        }
+    }
+
+    #[test]
+    fn rand_macro_by_reference() {
+        fn roll(rng: &mut Rng) -> i32 {
+            crate::rand!(rng, 1..=6)
+        }
+        fn roll_generic<R: Rand>(rng: &mut R) -> u64 {
+            crate::rand!(rng, 1..=6)
+        }
+        let mut rng = Rng::from_seed(0);
+        let mut reference = rng.clone();
+        for _ in 0..100 {
+            assert_eq!(roll(&mut rng), reference.rand_range_inclusive_i32(1, 6));
+            assert_eq!(roll_generic(&mut rng), reference.rand_range_inclusive_u64(1, 6));
+        }
+        let r = &mut rng;
+        assert!((1..=6).contains(&crate::rand!(r, 1..=6)));
+        assert!(crate::rand!(r, 0..3_u32) < 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rand_macro_empty() {
+        let mut rng = Rng::from_seed(0);
+        crate::rand!(rng, 3..3_u32);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn weighted_reservoir() {
+        let mut rng = Rng::from_seed(0);
+        let weights = [1.0, 2.0, 0.0, 4.0, 8.0, 1.0];
+        let mut hist = [0_i32; 6];
+        for _ in 0..10000 {
+            let s = rng.weighted_reservoir(weights.iter().copied().enumerate(), 2);
+            assert_eq!(s.len(), 2);
+            assert_ne!(s[0], s[1]);
+            for i in s {hist[i] += 1;}
+        }
+        assert_eq!(hist[2], 0);
+        assert!(hist[0] < hist[1] && hist[1] < hist[3] && hist[3] < hist[4]);
+        assert!((hist[0] - hist[5]).abs() < 200);
+        // For k = 1 the probabilities are proportional to the weights.
+        let mut hist = [0; 6];
+        for _ in 0..16000 {
+            let s = rng.weighted_reservoir(weights.iter().copied().enumerate(), 1);
+            hist[s[0]] += 1;
+        }
+        for (&h, &w) in hist.iter().zip(&weights) {
+            assert!((h as f64 - 1000.0*w).abs() < 300.0);
+        }
+        let s = rng.weighted_reservoir(vec![('a', 1.0), ('b', 0.0)].into_iter(), 5);
+        assert_eq!(s, vec!['a']);
+        assert!(rng.weighted_reservoir(vec![('a', 1.0)].into_iter(), 0).is_empty());
+    }
+
+    #[test]
+    fn xor_fill() {
+        let original: Vec<u8> = (0..37).collect();
+        let mut a = original.clone();
+        Rng::from_seed(5).xor_fill(&mut a);
+        assert_ne!(a, original);
+        Rng::from_seed(5).xor_fill(&mut a);
+        assert_eq!(a, original);
+        let mut zeros = [0; 37];
+        let mut bytes = [0; 37];
+        Rng::from_seed(5).xor_fill(&mut zeros);
+        Rng::from_seed(5).fill(&mut bytes);
+        assert_eq!(zeros, bytes);
+    }
+
+    #[test]
+    fn expected_rejections() {
+        use crate::expected_rejections;
+        for k in 0..64 {
+            assert_eq!(expected_rejections(1 << k), 0.0);
+        }
+        for &m in &[3, 5, 1000, (1 << 40) + 1, u64::MAX, (1 << 63) + 1] {
+            let r = expected_rejections(m);
+            assert!(r > 0.0 && r < 1.0);
+        }
+        assert_eq!(expected_rejections(3), 1.0/3.0);
+        assert_eq!(expected_rejections(0), f64::INFINITY);
+        // Compare with the observed count, one draw per sample is accepted.
+        let mut rng = Rng::from_seed(0);
+        let m = 5;
+        let (n, mut draws) = (10000, 0);
+        for _ in 0..n {draws += rng.rand_bounded_u32_counted(m).1;}
+        let observed = (draws - n) as f64/n as f64;
+        assert!((observed - expected_rejections(m as u64)).abs() < 0.05);
+    }
+
+    #[test]
+    fn hash_rng() {
+        use std::hash::{Hash, Hasher};
+        let hash = |rng: &Rng| {
+            let mut h = std::collections::hash_map::DefaultHasher::new();
+            rng.hash(&mut h);
+            h.finish()
+        };
+        let mut a = Rng::from_seed(0);
+        let b = Rng::from_seed(0);
+        assert_eq!(hash(&a), hash(&b));
+        a.rand_u64();
+        assert_ne!(hash(&a), hash(&b));
+        let set: std::collections::HashSet<Rng> = (0..10)
+            .map(|i| Rng::from_seed(i % 5)).collect();
+        assert_eq!(set.len(), 5);
+    }
+
+    #[test]
+    fn from_low_entropy() {
+        let first: Vec<u64> = (0..16)
+            .map(|seed| Rng::from_low_entropy(seed).rand_u64()).collect();
+        for i in 0..16 {
+            for j in 0..i {
+                let d = (first[i] ^ first[j]).count_ones();
+                assert!((12..=52).contains(&d), "seeds {} and {}: {}", i, j, d);
+            }
+        }
+        // Each bit is set in about half of the outputs.
+        for k in 0..64 {
+            let ones = first.iter().filter(|&&x| x >> k & 1 == 1).count();
+            assert!((1..16).contains(&ones));
+        }
+        assert!(Rng::from_low_entropy(3) == Rng::from_low_entropy(3));
+    }
+
+    #[test]
+    fn choice_array() {
+        const TABLE: [char; 3] = ['a', 'b', 'c'];
+        let mut rng = Rng::from_seed(0);
+        let mut hist = [0; 3];
+        for _ in 0..3000 {
+            let &c = rng.choice_array(&TABLE);
+            hist[(c as u8 - b'a') as usize] += 1;
+        }
+        assert!(hist.iter().all(|&h| (h - 1000_i32).abs() < 150));
+        assert_eq!(*rng.choice_array(&[7]), 7);
+    }
+
+    #[test]
+    fn sample_pmf() {
+        let mut rng = Rng::from_seed(0);
+        let pmf = [0.1, 0.4, 0.2999, 0.2, 0.0];
+        let mut hist = [0; 5];
+        for _ in 0..100000 {hist[rng.sample_pmf(&pmf)] += 1;}
+        assert_eq!(hist[4], 0);
+        for (&h, &p) in hist.iter().zip(&pmf) {
+            assert!((h as f64 - 100000.0*p/0.9999).abs() < 600.0);
+        }
+        // A near-maximal sample must not fall off the end.
+        assert_eq!(Constant(u32::MAX).sample_pmf(&pmf), 3);
+        assert_eq!(Constant(u32::MAX).sample_pmf(&[0.3, 0.7000001]), 1);
+    }
+
+    #[test]
+    fn full_jitter() {
+        let mut rng = Rng::from_seed(0);
+        for attempt in 0..200 {
+            for _ in 0..20 {
+                assert!(rng.full_jitter(100, 30000, attempt) <= 30000);
+                assert!(rng.full_jitter(100, 30000, attempt) <= 100 << attempt.min(20));
+                rng.full_jitter(u64::MAX/3, u64::MAX, attempt);
+            }
+        }
+        assert_eq!(rng.full_jitter(0, 1000, 5), 0);
+        assert_eq!(rng.full_jitter(100, 0, 5), 0);
+        let mut hist = [0; 5];
+        for _ in 0..5000 {hist[rng.full_jitter(1, 100, 2) as usize] += 1;}
+        assert!(hist.iter().all(|&h| (h - 1000_i32).abs() < 150));
+    }
+
+    #[test]
+    fn rand_ordering() {
+        use core::cmp::Ordering;
+        let index = |o: Ordering| (o as i32 + 1) as usize;
+        let mut rng = Rng::from_seed(0);
+        let mut hist = [0; 3];
+        for _ in 0..6000 {hist[index(rng.rand_ordering())] += 1;}
+        assert!(hist.iter().all(|&h| (h - 2000_i32).abs() < 200));
+        let mut hist = [0; 3];
+        for _ in 0..6000 {hist[index(rng.rand_ordering_weighted(1.0, 0.0, 2.0))] += 1;}
+        assert_eq!(hist[1], 0);
+        assert!((hist[2] - 4000_i32).abs() < 200);
+    }
+
+    #[test]
+    fn from_seed_warmed() {
+        let mut a = Rng::from_seed(0);
+        assert!(Rng::from_seed_warmed(0, 0) == a);
+        a.rand_u64();
+        a.rand_u64();
+        assert!(Rng::from_seed_warmed(0, 2) == a);
+        let first: Vec<u64> = (0..8)
+            .map(|w| Rng::from_seed_warmed(0, w).rand_u64()).collect();
+        for i in 0..8 {
+            for j in 0..i {assert_ne!(first[i], first[j]);}
+        }
+    }
+
+    #[test]
+    fn sample_cdf() {
+        let mut rng = Rng::from_seed(0);
+        let cdf = [0.1, 0.5, 0.5, 0.8, 1.0];
+        let pmf = [0.1, 0.4, 0.0, 0.3, 0.2];
+        let mut hist = [0; 5];
+        for _ in 0..100000 {hist[rng.sample_cdf(&cdf)] += 1;}
+        assert_eq!(hist[2], 0);
+        for (&h, &p) in hist.iter().zip(&pmf) {
+            assert!((h as f64 - 100000.0*p).abs() < 600.0);
+        }
+        assert_eq!(Constant(0).sample_cdf(&cdf), 0);
+        assert_eq!(Constant(u32::MAX).sample_cdf(&[0.5, 0.9999]), 1);
+        assert_eq!(rng.sample_cdf(&[1.0]), 0);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn sample_cdf_decreasing() {
+        Rng::from_seed(0).sample_cdf(&[0.5, 0.3, 1.0]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn choice_avoiding_recent() {
+        let mut rng = Rng::from_seed(0);
+        let mut hist = [0; 4];
+        for _ in 0..7000 {hist[rng.choice_avoiding_recent(4, &[1, 3, 1], 0.5)] += 1;}
+        // Weights 1, 0.5, 1, 0.5.
+        assert!((hist[0] - 2333_i32).abs() < 200 && (hist[2] - 2333_i32).abs() < 200);
+        assert!((hist[1] - 1167_i32).abs() < 150 && (hist[3] - 1167_i32).abs() < 150);
+        for _ in 0..100 {
+            assert_eq!(rng.choice_avoiding_recent(3, &[0, 2], 0.0), 1);
+            assert!(rng.choice_avoiding_recent(2, &[0, 1], 0.0) < 2);
+        }
+    }
+
+    #[test]
+    fn iter_until() {
+        let mut rng = Rng::from_seed(0);
+        let mut reference = rng.clone();
+        let a: Vec<u32> = rng.iter_until(|rng| rng.rand_bounded_u32(10), |&x| x >= 8).collect();
+        assert!(a[..a.len() - 1].iter().all(|&x| x < 8));
+        assert!(*a.last().unwrap() >= 8);
+        let b: Vec<u32> = (0..a.len()).map(|_| reference.rand_bounded_u32(10)).collect();
+        assert_eq!(a, b);
+        assert!(rng == reference);
+        assert_eq!(rng.iter_until(Rand::rand_u32, |_| true).count(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    /// The Ziggurat tables for 256 layers of area `v` under a decreasing
+    /// density `f` on `[0, inf)` with the tail beyond `r`.
+    fn ziggurat(r: f64, v: f64, f: fn(f64) -> f64, f_inv: fn(f64) -> f64)
+    -> ([f64; 257], [f64; 257])
+    {
+        let mut x = [0.0; 257];
+        x[0] = v/f(r);
+        x[1] = r;
+        for i in 1..255 {
+            x[i + 1] = f_inv(f(x[i]) + v/x[i]);
+        }
+        let mut y = [0.0; 257];
+        for i in 0..256 {y[i] = f(x[i]);}
+        y[256] = 1.0;
+        (x, y)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{Rng, Rand};
+    #[cfg(feature = "std")]
+    #[test]
+    fn ziggurat_normal_tables() {
+        let z = ziggurat(crate::ZIGGURAT_NORMAL_R, 0.00492867323399,
+            |x| f64::exp(-0.5*x*x), |y| f64::sqrt(-2.0*f64::ln(y)));
+        assert_eq!(z.0, crate::ZIGGURAT_NORMAL_X);
+        assert_eq!(z.1, crate::ZIGGURAT_NORMAL_Y);
+        // The recurrence closes at the top: the last layer has area v.
+        let (x, y) = (&crate::ZIGGURAT_NORMAL_X, &crate::ZIGGURAT_NORMAL_Y);
+        assert!((x[255]*(1.0 - y[255]) - 0.00492867323399).abs() < 1E-9);
+        assert_eq!((x[256], y[256]), (0.0, 1.0));
+    }
 
-    fn rng_test<Generator: Rand>() {
-        let mut rng = Generator::from_seed(0);
-        println!("{:?}", rng.rand_range_u32(1, 6));
+    #[cfg(feature = "std")]
+    #[test]
+    fn rand_normal_ziggurat() {
+        let mut rng = Rng::from_seed(0);
+        let n = 200000;
+        let moments = |a: &[f64]| {
+            let (mean, var) = mean_variance(a.iter().copied());
+            let m4 = a.iter().map(|&x| x.powi(4)).sum::<f64>()/n as f64;
+            let tail = a.iter().filter(|&&x| x > 2.0).count() as f64/n as f64;
+            (mean, var, m4, tail)
+        };
+        let zig: Vec<f64> = (0..n).map(|_| rng.rand_normal_ziggurat()).collect();
+        let polar: Vec<f64> = (0..n).map(|_| rng.rand_normal_f64(0.0, 1.0)).collect();
+        let (m1, v1, k1, t1) = moments(&zig);
+        let (m2, v2, k2, t2) = moments(&polar);
+        assert!(m1.abs() < 0.01 && m2.abs() < 0.01);
+        assert!((v1 - 1.0).abs() < 0.015 && (v2 - 1.0).abs() < 0.015);
+        assert!((k1 - 3.0).abs() < 0.1 && (k2 - 3.0).abs() < 0.1);
+        // P(X > 2) = 0.02275.
+        assert!((t1 - 0.02275).abs() < 0.002 && (t1 - t2).abs() < 0.003);
+        let far = zig.iter().filter(|&&x| x.abs() > crate::ZIGGURAT_NORMAL_R).count();
+        assert!(far > 0 && far < 100);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn ziggurat_exp_tables() {
+        let z = ziggurat(crate::ZIGGURAT_EXP_R, 0.003949659822581557,
+            |x| f64::exp(-x), |y| -f64::ln(y));
+        assert_eq!(z.0, crate::ZIGGURAT_EXP_X);
+        assert_eq!(z.1, crate::ZIGGURAT_EXP_Y);
+        let (x, y) = (&crate::ZIGGURAT_EXP_X, &crate::ZIGGURAT_EXP_Y);
+        assert!((x[255]*(1.0 - y[255]) - 0.003949659822581557).abs() < 1E-9);
+        assert_eq!((x[256], y[256]), (0.0, 1.0));
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn test0() {
-        rng_test::<Rng>();
+    fn rand_exp_ziggurat() {
+        let mut rng = Rng::from_seed(0);
+        let n = 200000;
+        let zig: Vec<f64> = (0..n).map(|_| rng.rand_exp_ziggurat()).collect();
+        let inv: Vec<f64> = (0..n).map(|_| -f64::ln(1.0 - rng.rand_f64())).collect();
+        let (mean, var) = mean_variance(zig.iter().copied());
+        assert!((mean - 1.0).abs() < 0.01 && (var - 1.0).abs() < 0.03);
+        assert!(zig.iter().all(|&x| x >= 0.0));
+        // Compare the empirical distribution functions at a few points.
+        for &t in &[0.1, 0.5, 1.0, 2.0, 4.0, 8.0] {
+            let p1 = zig.iter().filter(|&&x| x < t).count() as f64/n as f64;
+            let p2 = inv.iter().filter(|&&x| x < t).count() as f64/n as f64;
+            let p = 1.0 - f64::exp(-t);
+            assert!((p1 - p).abs() < 0.004 && (p1 - p2).abs() < 0.005, "t = {}", t);
+        }
+        let (mean, _) = mean_variance((0..n).map(|_| rng.rand_exp_f64(4.0)));
+        assert!((mean - 0.25).abs() < 0.003);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn choice_weighted_map() {
+        let mut rng = Rng::from_seed(0);
+        let mut map = std::collections::HashMap::new();
+        assert_eq!(rng.choice_weighted_map(&map), None);
+        map.insert("a", 1.0);
+        map.insert("b", 3.0);
+        map.insert("c", 0.0);
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..8000 {
+            *counts.entry(*rng.choice_weighted_map(&map).unwrap()).or_insert(0) += 1;
+        }
+        assert_eq!(counts.get("c"), None);
+        assert!((counts["a"] - 2000_i32).abs() < 200);
+        assert!((counts["b"] - 6000_i32).abs() < 200);
+    }
+
+    #[test]
+    fn rand_near_modular() {
+        let mut rng = Rng::from_seed(0);
+        let ring_distance = |a: usize, b: usize, n: usize| {
+            let d = (a + n - b) % n;
+            d.min(n - d)
+        };
+        let mut hist = [0; 10];
+        for _ in 0..7000 {
+            let x = rng.rand_near_modular(1, 3, 10);
+            assert!(ring_distance(x, 1, 10) <= 3);
+            hist[x] += 1;
+        }
+        // The window 8, 9, 0, 1, 2, 3, 4 wraps around zero.
+        for (i, &h) in hist.iter().enumerate() {
+            if ring_distance(i, 1, 10) <= 3 {
+                assert!((h - 1000_i32).abs() < 150);
+            } else {
+                assert_eq!(h, 0);
+            }
+        }
+        assert_eq!(rng.rand_near_modular(25, 0, 10), 5);
+        assert_eq!(rng.rand_near_modular(7, 100, 1), 0);
+        let x = rng.rand_near_modular(usize::MAX, usize::MAX/2, usize::MAX);
+        assert!(x < usize::MAX);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn random_maze() {
+        let mut rng = Rng::from_seed(0);
+        assert_eq!(rng.random_maze(0, 3), vec![true; 7]);
+        for &(w, h) in &[(1, 1), (1, 5), (4, 3), (7, 7), (10, 2)] {
+            let grid = rng.random_maze(w, h);
+            let (gw, gh) = (2*w + 1, 2*h + 1);
+            assert_eq!(grid.len(), gw*gh);
+            // The border and the corners between cells are walls.
+            for y in 0..gh {
+                for x in 0..gw {
+                    if x == 0 || y == 0 || x == gw - 1 || y == gh - 1
+                        || (x % 2 == 0 && y % 2 == 0) {assert!(grid[y*gw + x]);}
+                    if x % 2 == 1 && y % 2 == 1 {assert!(!grid[y*gw + x]);}
+                }
+            }
+            // A tree: w*h - 1 passages and every cell reachable.
+            let passages = (0..gw*gh).filter(|&i| !grid[i]).count() - w*h;
+            assert_eq!(passages, w*h - 1);
+            let mut seen = vec![false; gw*gh];
+            let mut stack = vec![gw + 1];
+            seen[gw + 1] = true;
+            while let Some(i) = stack.pop() {
+                for j in [i - 1, i + 1, i - gw, i + gw] {
+                    if !grid[j] && !seen[j] {seen[j] = true; stack.push(j);}
+                }
+            }
+            assert!((0..gw*gh).all(|i| grid[i] || seen[i]));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "choice: slice must be non-empty")]
+    fn choice_empty() {
+        let a: [u32; 0] = [];
+        Rng::from_seed(0).choice(&a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn mixture() {
+        let mut rng = Rng::from_seed(0);
+        let m = crate::Mixture::new(vec![
+            (1.0, Box::new(|rng: &mut Rng| rng.rand_normal_f64(-3.0, 1.0))),
+            (1.0, Box::new(|rng: &mut Rng| rng.rand_normal_f64(3.0, 1.0))),
+            (0.0, Box::new(|_: &mut Rng| 100.0))
+        ]);
+        let mut hist = [0; 12];
+        let (mean, var) = mean_variance((0..20000).map(|_| {
+            let x = m.sample(&mut rng);
+            assert!(x != 100.0);
+            if (-6.0..6.0).contains(&x) {hist[(x + 6.0) as usize] += 1;}
+            x
+        }));
+        assert!(mean.abs() < 0.1);
+        assert!((var - 10.0).abs() < 0.3);
+        // Two modes at -3 and 3, with a valley at 0 in between.
+        assert!(hist[2] > 2*hist[5] && hist[3] > 2*hist[6]);
+        assert!(hist[8] > 2*hist[5] && hist[9] > 2*hist[6]);
+        let single = crate::Mixture::new(vec![(2.0, Box::new(|_: &mut Rng| 1.5))]);
+        assert_eq!(single.sample(&mut rng), 1.5);
+    }
+
+    #[test]
+    fn range_f64_rounding() {
+        let (a, b) = (1E16, 1E16 + 2.0);
+        let x = Constant(u32::MAX).rand_range_f64(a, b);
+        assert!(a <= x && x < b);
+        assert_eq!(x, 1E16);
+        assert_eq!(Constant(u32::MAX).rand_range_f64(-1.0, 0.0), -1.0/4294967296.0);
+        assert_eq!(Constant(0).rand_range_f64(a, b), a);
+        assert_eq!(crate::prev_float(1.0), 1.0 - f64::EPSILON/2.0);
+        assert_eq!(crate::prev_float(0.0), -f64::from_bits(1));
+        assert_eq!(crate::prev_float(-1.0), -1.0 - f64::EPSILON);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn loguniform_rounding() {
+        let (lo, hi) = (0.1 - 1E-12, 0.1);
+        let x = Constant(u32::MAX).rand_loguniform_f64(lo, hi);
+        assert!(lo <= x && x < hi, "{}", x);
+        let x = Constant(0).rand_loguniform_f64(lo, hi);
+        assert!(lo <= x && x < hi, "{}", x);
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..10000 {
+            let x = rng.rand_loguniform_f64(lo, hi);
+            assert!(lo <= x && x < hi);
+        }
     }
 }